@@ -0,0 +1,240 @@
+use crate::sift;
+use crate::MinHeap;
+use std::cmp::Ord;
+
+// The arity of the layout used by large heaps. Wider nodes make the tree shallower,
+// so a sift touches fewer cache lines, at the cost of more comparisons per level.
+const WIDE_ARITY: usize = 4;
+
+/// An adaptive heap is a min-heap that changes its layout with its size.
+/// It starts out as a binary heap and, once it grows past a threshold, rebuilds itself
+/// as a 4-ary heap, which causes fewer cache misses on large heaps. It turns back into
+/// a binary heap when it shrinks below a second, smaller threshold. The gap between the
+/// two thresholds keeps a heap whose size hovers around one of them from migrating back
+/// and forth. Each migration rebuilds the heap once, in O(n).
+#[derive(Clone)]
+pub struct AdaptiveHeap<T: Ord> {
+    values: Vec<T>,
+    arity: usize,
+    grow_threshold: usize,
+    shrink_threshold: usize,
+}
+
+impl<T: Ord> AdaptiveHeap<T> {
+    /// Returns an empty adaptive heap. It switches to the 4-ary layout when it holds
+    /// more than grow_threshold elements, and back to the binary layout when it holds
+    /// less than shrink_threshold elements.
+    /// Panics if shrink_threshold is not smaller than grow_threshold.
+    pub fn new(grow_threshold: usize, shrink_threshold: usize) -> Self {
+        assert!(
+            shrink_threshold < grow_threshold,
+            "the shrink threshold must be smaller than the grow threshold"
+        );
+        AdaptiveHeap {
+            values: vec![],
+            arity: 2,
+            grow_threshold,
+            shrink_threshold,
+        }
+    }
+
+    /// Returns the number of sons of every node in the current layout, 2 or 4.
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    // Switches to the given arity and rebuilds the heap for the new layout.
+    fn migrate(&mut self, arity: usize) {
+        self.arity = arity;
+        if arity == 2 {
+            sift::heapify(&mut self.values, &mut T::cmp);
+        } else if self.values.len() > 1 {
+            for index in (0..=(self.values.len() - 2) / arity).rev() {
+                self.sift_down(index);
+            }
+        }
+    }
+
+    // The binary layout uses the shared sifts. The sift module hard-codes the binary
+    // son and father indexes, so the wide layout keeps its own d-ary loops.
+    fn sift_up(&mut self, mut index: usize) {
+        if self.arity == 2 {
+            return sift::sift_up(&mut self.values, index, &mut T::cmp);
+        }
+
+        while index > 0 {
+            let father = (index - 1) / self.arity;
+            if self.values[index] >= self.values[father] {
+                break;
+            }
+            self.values.swap(index, father);
+            index = father;
+        }
+    }
+
+    fn sift_down(&mut self, mut index: usize) {
+        if self.arity == 2 {
+            return sift::sift_down(&mut self.values, index, &mut T::cmp);
+        }
+
+        let len = self.values.len();
+        loop {
+            // Stop if the current node doesn't have any children.
+            let first_son = self.arity * index + 1;
+            if first_son >= len {
+                break;
+            }
+
+            // Find the smallest child.
+            let last_son = (first_son + self.arity).min(len);
+            let mut smallest = first_son;
+            for son in first_son + 1..last_son {
+                if self.values[son] < self.values[smallest] {
+                    smallest = son;
+                }
+            }
+
+            // Stop if the current node is where it's supposed to be.
+            if self.values[index] <= self.values[smallest] {
+                break;
+            }
+
+            self.values.swap(index, smallest);
+            index = smallest;
+        }
+    }
+}
+
+impl<T: Ord> MinHeap<T> for AdaptiveHeap<T> {
+    fn push(&mut self, value: T) {
+        self.values.push(value);
+        let last = self.values.len() - 1;
+        self.sift_up(last);
+
+        if self.arity == 2 && self.values.len() > self.grow_threshold {
+            self.migrate(WIDE_ARITY);
+        }
+    }
+
+    fn top(&self) -> Option<&T> {
+        self.values.first()
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let mut top = self.values.pop()?;
+        if !self.values.is_empty() {
+            top = std::mem::replace(&mut self.values[0], top);
+            self.sift_down(0);
+        }
+
+        if self.arity != 2 && self.values.len() < self.shrink_threshold {
+            self.migrate(2);
+        }
+        Some(top)
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_heap::BinaryHeap;
+    use crate::testing;
+    use rand::prelude::*;
+
+    fn is_valid<T: Ord>(h: &AdaptiveHeap<T>) -> bool {
+        (1..h.values.len()).all(|i| h.values[(i - 1) / h.arity] <= h.values[i])
+    }
+
+    #[test]
+    #[should_panic]
+    fn thresholds_must_leave_a_gap() {
+        AdaptiveHeap::<i32>::new(10, 10);
+    }
+
+    #[test]
+    fn sorts_correctly() {
+        let mut rng = thread_rng();
+        let input: Vec<i32> = (0..1000).map(|_| rng.gen_range(-100..100)).collect();
+        testing::assert_sorts_correctly(AdaptiveHeap::new(100, 50), &input);
+    }
+
+    #[test]
+    fn migrates_when_growing_and_shrinking() {
+        let mut rng = thread_rng();
+        let mut h = AdaptiveHeap::new(64, 16);
+        let mut values: Vec<i32> = (0..200).map(|_| rng.gen_range(-1000..1000)).collect();
+
+        for (i, &v) in values.iter().enumerate() {
+            h.push(v);
+            assert_eq!(h.arity(), if i < 64 { 2 } else { 4 });
+            assert!(is_valid(&h));
+        }
+
+        values.sort();
+        for (i, v) in values.into_iter().enumerate() {
+            assert_eq!(h.pop(), Some(v));
+            assert!(is_valid(&h));
+            let remaining = 199 - i;
+            assert_eq!(h.arity(), if remaining < 16 { 2 } else { 4 });
+        }
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn equals_binary_heap_with_same_elements() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..300).map(|_| rng.gen_range(-20..20)).collect();
+        let mut adaptive = AdaptiveHeap::new(100, 50);
+        for &v in &values {
+            adaptive.push(v);
+        }
+        assert_eq!(adaptive.arity(), 4);
+
+        // Same multiset, different layouts.
+        let mut binary = BinaryHeap::from_vec(values.clone());
+        assert!(adaptive.equals_multiset(&binary));
+        assert!(binary.equals_multiset(&adaptive));
+
+        // A single different occurrence is detected.
+        binary.pop();
+        binary.push(1000);
+        assert!(!adaptive.equals_multiset(&binary));
+        binary.push(0);
+        assert!(!adaptive.equals_multiset(&binary));
+
+        let mut sorted = values;
+        sorted.sort();
+        assert_eq!(adaptive.sorted_elements(), sorted);
+        assert_eq!(adaptive.len(), 300);
+    }
+
+    #[test]
+    fn stays_correct_across_repeated_migrations() {
+        let mut rng = thread_rng();
+        let mut ours = AdaptiveHeap::new(40, 20);
+        let mut theirs = std::collections::BinaryHeap::new();
+
+        for round in 0..20 {
+            // Alternate between growing well past and shrinking well below the thresholds.
+            let grow = round % 2 == 0;
+            for _ in 0..60 {
+                if grow || ours.is_empty() {
+                    let v: i32 = rng.gen_range(-500..500);
+                    ours.push(v);
+                    theirs.push(std::cmp::Reverse(v));
+                } else {
+                    assert_eq!(ours.pop(), theirs.pop().map(|std::cmp::Reverse(v)| v));
+                }
+                assert!(is_valid(&ours));
+            }
+        }
+    }
+}
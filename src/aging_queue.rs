@@ -0,0 +1,151 @@
+use crate::binary_heap::BinaryHeap;
+use crate::MinHeap;
+use std::cmp::Ordering;
+
+// A queued value. Entries are ordered by their aging key, then by insertion order.
+struct Entry<V> {
+    key: u128,
+    seq: u64,
+    priority: u64,
+    value: V,
+}
+
+impl<V> PartialEq for Entry<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<V> Eq for Entry<V> {}
+
+impl<V> PartialOrd for Entry<V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<V> Ord for Entry<V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.key, self.seq).cmp(&(other.key, other.seq))
+    }
+}
+
+/// A priority queue where waiting values gain priority over time, so none starves.
+/// Smaller priorities are popped first, and on every tick the effective priority of a
+/// value drops by age_factor, so a value with a large priority eventually gets ahead
+/// of a steady stream of values with small priorities.
+///
+/// The effective priority of a value pushed at time t is priority - age_factor * (now - t).
+/// As now is the same for every value, this orders values like priority + age_factor * t,
+/// which never changes, so ticking is O(1) and the heap never has to be rebuilt.
+pub struct AgingPriorityQueue<V> {
+    entries: BinaryHeap<Entry<V>>,
+    age_factor: u64,
+    now: u64,
+    next_seq: u64,
+}
+
+impl<V> AgingPriorityQueue<V> {
+    /// Returns an empty queue, where waiting one tick lowers priorities by age_factor.
+    pub fn new(age_factor: u64) -> Self {
+        AgingPriorityQueue {
+            entries: BinaryHeap::new(),
+            age_factor,
+            now: 0,
+            next_seq: 0,
+        }
+    }
+
+    /// Insert a value with the given priority.
+    pub fn push(&mut self, priority: u64, value: V) {
+        let key = priority as u128 + self.age_factor as u128 * self.now as u128;
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.entries.push(Entry {
+            key,
+            seq,
+            priority,
+            value,
+        });
+    }
+
+    /// Returns the value with the smallest effective priority along with its original
+    /// priority, and removes it from the queue.
+    /// Return None if the queue is empty.
+    pub fn pop(&mut self) -> Option<(u64, V)> {
+        self.entries
+            .pop()
+            .map(|entry| (entry.priority, entry.value))
+    }
+
+    /// Advances the time by one tick, which ages every waiting value.
+    pub fn tick(&mut self) {
+        self.now += 1;
+    }
+
+    /// Returns the number of ticks so far.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Returns the number of queued values.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no values are queued.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Pushes a low priority value, then on every tick pushes a high priority value and
+    // pops one. Returns the tick at which the low priority value was popped.
+    fn tick_when_low_priority_pops(age_factor: u64, ticks: u64) -> Option<u64> {
+        let mut q = AgingPriorityQueue::new(age_factor);
+        q.push(100, "low");
+        for _ in 0..ticks {
+            q.tick();
+            q.push(0, "high");
+            q.push(0, "high");
+            if q.pop() == Some((100, "low")) {
+                return Some(q.now());
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn aged_values_eventually_pop() {
+        // The low priority value was effectively at 100 - 10 * (t - 0), which beats
+        // the new arrivals at 0 once t passes 10. Older high priority values that
+        // are still queued age as well, which delays it a few more ticks.
+        let tick = tick_when_low_priority_pops(10, 1000).unwrap();
+        assert!(tick > 10 && tick < 30, "popped at tick {}", tick);
+    }
+
+    #[test]
+    fn values_starve_without_aging() {
+        assert_eq!(tick_when_low_priority_pops(0, 1000), None);
+    }
+
+    #[test]
+    fn equal_priorities_pop_in_fifo_order() {
+        let mut q = AgingPriorityQueue::new(1);
+        assert!(q.is_empty());
+        q.push(5, 'a');
+        q.push(5, 'b');
+        q.tick();
+        q.push(4, 'c');
+        q.push(3, 'd');
+        assert_eq!(q.len(), 4);
+
+        // c, pushed one tick later with a priority one lower, ties with a and b.
+        let popped: Vec<char> = std::iter::from_fn(|| q.pop()).map(|(_, v)| v).collect();
+        assert_eq!(popped, vec!['d', 'a', 'b', 'c']);
+    }
+}
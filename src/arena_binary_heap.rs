@@ -0,0 +1,204 @@
+use crate::sift;
+use crate::MinHeap;
+use std::cmp::Ord;
+
+/// An arena binary heap is a min-heap that stores its elements in an arena and
+/// keeps a binary heap of arena indices. Sifting only moves the indices, so
+/// elements are never moved once inserted, which is faster for large elements.
+#[derive(Clone)]
+pub struct ArenaBinaryHeap<T: Ord> {
+    arena: Vec<Option<T>>,
+    // Arena slots that were emptied by pop and can be reused.
+    free: Vec<usize>,
+    // The heap of arena indices.
+    indices: Vec<usize>,
+}
+
+impl<T: Ord> ArenaBinaryHeap<T> {
+    /// Returns an empty arena binary heap.
+    pub fn new() -> Self {
+        ArenaBinaryHeap {
+            arena: vec![],
+            free: vec![],
+            indices: vec![],
+        }
+    }
+
+    // The sifts move the indices and compare the elements they point to.
+    fn sift_up(&mut self, pos: usize) {
+        let arena = &self.arena;
+        sift::sift_up(&mut self.indices, pos, &mut |a, b| {
+            Self::slot(arena, *a).cmp(Self::slot(arena, *b))
+        });
+    }
+
+    fn sift_down(&mut self, pos: usize) {
+        let arena = &self.arena;
+        sift::sift_down(&mut self.indices, pos, &mut |a, b| {
+            Self::slot(arena, *a).cmp(Self::slot(arena, *b))
+        });
+    }
+
+    // Returns the element in an occupied arena slot.
+    fn slot(arena: &[Option<T>], slot: usize) -> &T {
+        arena[slot].as_ref().unwrap()
+    }
+}
+
+impl<T: Ord> Default for ArenaBinaryHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> MinHeap<T> for ArenaBinaryHeap<T> {
+    fn push(&mut self, value: T) {
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.arena[slot] = Some(value);
+                slot
+            }
+            None => {
+                self.arena.push(Some(value));
+                self.arena.len() - 1
+            }
+        };
+        self.indices.push(slot);
+        self.sift_up(self.indices.len() - 1);
+    }
+
+    fn top(&self) -> Option<&T> {
+        let slot = *self.indices.first()?;
+        self.arena[slot].as_ref()
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let last = self.indices.pop()?;
+        let slot = match self.indices.first_mut() {
+            Some(root) => std::mem::replace(root, last),
+            None => last,
+        };
+        self.sift_down(0);
+
+        self.free.push(slot);
+        self.arena[slot].take()
+    }
+
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use rand::prelude::*;
+
+    // A large element, that is expensive to move.
+    #[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+    struct Large {
+        key: i32,
+        payload: [u64; 32],
+    }
+
+    fn large(key: i32) -> Large {
+        Large {
+            key,
+            payload: [key as u64; 32],
+        }
+    }
+
+    #[test]
+    fn empty_heap_is_empty() {
+        let mut h = ArenaBinaryHeap::<i32>::new();
+        assert!(h.is_empty());
+        assert_eq!(h.len(), 0);
+        assert!(h.top().is_none());
+        assert!(h.pop().is_none());
+    }
+
+    #[test]
+    fn sorts_correctly() {
+        let mut rng = thread_rng();
+        let input: Vec<i32> = (0..1000).map(|_| rng.gen_range(-100..100)).collect();
+        testing::assert_sorts_correctly(ArenaBinaryHeap::new(), &input);
+    }
+
+    #[test]
+    fn large_elements_pop_in_order() {
+        let mut rng = thread_rng();
+        let mut h = ArenaBinaryHeap::new();
+        let mut keys: Vec<i32> = (0..500).map(|_| rng.gen_range(-1000..1000)).collect();
+        for &key in &keys {
+            h.push(large(key));
+        }
+        keys.sort();
+
+        for key in keys {
+            assert_eq!(h.pop(), Some(large(key)));
+        }
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn sifts_move_only_indices() {
+        let mut h = ArenaBinaryHeap::new();
+        for key in [50, 40, 30, 20, 10] {
+            h.push(large(key));
+        }
+        let slot_of = |h: &ArenaBinaryHeap<Large>, key: i32| {
+            h.arena
+                .iter()
+                .position(|v| v.as_ref().is_some_and(|v| v.key == key))
+        };
+
+        // Every element stays in the arena slot it was pushed into.
+        h.push(large(0));
+        assert_eq!(h.pop().unwrap().key, 0);
+        assert_eq!(h.pop().unwrap().key, 10);
+        for (slot, key) in [(0, 50), (1, 40), (2, 30), (3, 20)] {
+            assert_eq!(slot_of(&h, key), Some(slot));
+        }
+
+        // Freed slots are reused.
+        h.push(large(5));
+        h.push(large(7));
+        assert_eq!(h.arena.len(), 6);
+        assert_eq!(h.top().unwrap().key, 5);
+    }
+
+    #[test]
+    fn matches_std_heap_on_random_operations() {
+        let mut rng = thread_rng();
+        let mut ours = ArenaBinaryHeap::new();
+        let mut theirs = std::collections::BinaryHeap::new();
+
+        for _ in 0..20000 {
+            if !ours.is_empty() && rng.gen_bool(0.4) {
+                assert_eq!(ours.pop(), theirs.pop().map(|std::cmp::Reverse(v)| v));
+            } else {
+                let v: i32 = rng.gen();
+                ours.push(v);
+                theirs.push(std::cmp::Reverse(v));
+            }
+            assert_eq!(ours.len(), theirs.len());
+        }
+    }
+
+    #[test]
+    fn sorted_elements_skip_freed_slots() {
+        let mut h = ArenaBinaryHeap::new();
+        for v in [4, 1, 3, 2] {
+            h.push(v);
+        }
+        h.pop();
+        assert_eq!(h.sorted_elements(), vec![2, 3, 4]);
+        assert_eq!(h.len(), 3);
+        assert!(h.equals_multiset(&crate::binary_heap::BinaryHeap::from_vec(vec![3, 4, 2])));
+    }
+}
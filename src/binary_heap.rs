@@ -1,254 +1,2674 @@
+use crate::sift::{self, father};
+use crate::top_k::TopKExt;
 use crate::MinHeap;
-use std::cmp::Ord;
+use std::cmp::{Ord, Ordering};
+use std::collections::TryReserveError;
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::mem;
+use std::slice;
 
 /// A binary heap is an implementation of a min-heap using a binary tree.
+#[derive(Clone)]
 pub struct BinaryHeap<T: Ord> {
     values: Vec<T>,
+    auto_shrink: bool,
+    deferred_rebuild: bool,
+    // Set when an edit was made without restoring the heap property, either in deferred
+    // rebuild mode or by swap_top.
+    dirty: bool,
+    ceiling: Option<usize>,
 }
 
-fn left_son(index: usize) -> usize {
-    2 * index + 1
+// Counts the rebuilds, so tests can check that deferred edits share one.
+#[cfg(test)]
+thread_local! {
+    static REBUILDS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+// Capacity below which an auto-shrinking heap never shrinks.
+const AUTO_SHRINK_MIN_CAPACITY: usize = 64;
+
+// The sift helpers, ordering the elements by Ord.
+fn sift_up<T: Ord>(values: &mut [T], index: usize) {
+    sift::sift_up(values, index, &mut T::cmp);
+}
+fn sift_down<T: Ord>(values: &mut [T], index: usize) {
+    sift::sift_down(values, index, &mut T::cmp);
+}
+fn heapify<T: Ord>(values: &mut [T]) {
+    sift::heapify(values, &mut T::cmp);
+}
+fn is_heap<T: Ord>(values: &[T]) -> bool {
+    sift::is_heap(values, &mut T::cmp)
+}
+
+impl<T: Ord> BinaryHeap<T> {
+    /// Returns an empty binary heap.
+    pub fn new() -> Self {
+        BinaryHeap::from_raw(vec![])
+    }
+
+    /// Returns an empty binary heap that can release memory as it is drained.
+    /// When enabled, if after a pop less than a quarter of the capacity is used,
+    /// the capacity is shrunk to twice the number of elements.
+    /// Small heaps, with a capacity of at most 64 elements, are never shrunk.
+    pub fn with_auto_shrink(enabled: bool) -> Self {
+        BinaryHeap {
+            auto_shrink: enabled,
+            ..BinaryHeap::new()
+        }
+    }
+
+    /// Returns an empty binary heap that can defer rebuilding the heap after edits.
+    /// When enabled, edits that rebuild the heap, like retain_count and map_in_place,
+    /// only mark it as dirty, and the next operation that needs the heap order
+    /// rebuilds it once. This makes a batch of edits cost a single rebuild.
+    /// While the heap is dirty, top() scans all the elements in O(n).
+    pub fn with_deferred_rebuild(enabled: bool) -> Self {
+        BinaryHeap {
+            deferred_rebuild: enabled,
+            ..BinaryHeap::new()
+        }
+    }
+
+    /// Returns an empty binary heap that never holds more than ceiling elements.
+    /// Nothing is allocated upfront, and the capacity grows as usual but never past the ceiling.
+    /// Once the heap is full, pushing evicts the largest element, see push_clamped.
+    pub fn with_capacity_ceiling(ceiling: usize) -> Self {
+        BinaryHeap {
+            ceiling: Some(ceiling),
+            ..BinaryHeap::new()
+        }
+    }
+
+    // Returns a heap backed by the given values, which must satisfy the heap property.
+    fn from_raw(values: Vec<T>) -> Self {
+        BinaryHeap {
+            values,
+            auto_shrink: false,
+            deferred_rebuild: false,
+            dirty: false,
+            ceiling: None,
+        }
+    }
+
+    /// Returns a binary heap containing the given values.
+    /// This runs in O(n), which is faster than pushing the values one by one.
+    pub fn from_vec(mut values: Vec<T>) -> Self {
+        heapify(&mut values);
+        BinaryHeap::from_raw(values)
+    }
+
+    /// Returns a binary heap backed by the given values, without heapifying them.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that the values already satisfy the heap property:
+    /// no value is smaller than its father, where the father of index i is (i - 1) / 2.
+    /// Otherwise, the heap will return values in an unspecified order.
+    pub unsafe fn from_vec_unchecked(values: Vec<T>) -> Self {
+        debug_assert!(is_heap(&values));
+        BinaryHeap::from_raw(values)
+    }
+
+    /// Returns a binary heap backed by the given values, if they already satisfy the heap property.
+    /// This checks the values in O(n) and returns them back if they are not a valid heap.
+    pub fn from_vec_validated(values: Vec<T>) -> Result<Self, Vec<T>> {
+        if !is_heap(&values) {
+            return Err(values);
+        }
+        Ok(BinaryHeap::from_raw(values))
+    }
+
+    /// Consumes the heap and returns its backing storage, in heap order.
+    /// The storage can be turned back into a heap without reallocating or heapifying,
+    /// with from_vec_validated, or with from_vec_unchecked if it wasn't modified.
+    pub fn into_parts(mut self) -> Vec<T> {
+        self.flush();
+        self.values
+    }
+
+    /// Returns a binary heap backed by the given values, if they are sorted in ascending order.
+    /// A sorted Vec is already a valid heap, so it is only checked in O(n), and never moved.
+    /// Returns the values back if they are not sorted.
+    pub fn from_sorted_vec(values: Vec<T>) -> Result<Self, Vec<T>> {
+        if !values.windows(2).all(|w| w[0] <= w[1]) {
+            return Err(values);
+        }
+        Ok(BinaryHeap::from_raw(values))
+    }
+
+    /// Returns a binary heap containing the elements of the iterator, as long as there are
+    /// at most max of them. Returns an error as soon as the iterator yields more elements,
+    /// so an untrusted iterator can't make the heap allocate without bounds.
+    pub fn from_iter_capped<I: IntoIterator<Item = T>>(
+        iter: I,
+        max: usize,
+    ) -> Result<Self, CapacityError> {
+        let mut iter = iter.into_iter();
+        let (lower, _) = iter.size_hint();
+        if lower > max {
+            return Err(CapacityError { max });
+        }
+
+        let mut values = Vec::with_capacity(lower);
+        values.extend(iter.by_ref().take(max));
+        if iter.next().is_some() {
+            return Err(CapacityError { max });
+        }
+        Ok(BinaryHeap::from_vec(values))
+    }
+
+    /// Returns a binary heap containing the elements of all the given heaps.
+    /// The backing storages are concatenated and heapified once, in O(n).
+    pub fn meld_all(heaps: Vec<BinaryHeap<T>>) -> Self {
+        let total = heaps.iter().map(BinaryHeap::len).sum();
+        let mut values = Vec::with_capacity(total);
+        for heap in heaps {
+            values.extend(heap.values);
+        }
+        BinaryHeap::from_vec(values)
+    }
+
+    /// Returns a binary heap containing the elements of all the given iterators.
+    /// This is concat for a Vec of iterators.
+    pub fn from_iters<I: IntoIterator<Item = T>>(iters: Vec<I>) -> Self {
+        BinaryHeap::concat(iters)
+    }
+
+    /// Returns a binary heap containing the elements of all the given sources.
+    /// The elements are collected into one Vec, reserved from the combined size
+    /// hints, and heapified once.
+    pub fn concat<II, I>(sources: II) -> Self
+    where
+        II: IntoIterator<Item = I>,
+        I: IntoIterator<Item = T>,
+    {
+        let iters: Vec<I::IntoIter> = sources.into_iter().map(IntoIterator::into_iter).collect();
+        let total = iters.iter().map(|iter| iter.size_hint().0).sum();
+        let mut values = Vec::with_capacity(total);
+        for iter in iters {
+            values.extend(iter);
+        }
+        BinaryHeap::from_vec(values)
+    }
+
+    /// Returns a read-only view of the heap, for APIs that must not modify it.
+    pub fn view(&self) -> HeapView<'_, T> {
+        HeapView { heap: self }
+    }
+
+    /// Returns an iterator over the elements of the heap, in no particular order.
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.values.iter()
+    }
+
+    /// Returns an iterator over the elements of the heap, in ascending order.
+    /// The elements are cloned once upfront, and the heap is left unchanged.
+    pub fn iter_sorted(&self) -> IterSorted<'_, T>
+    where
+        T: Clone,
+    {
+        IterSorted {
+            // A dirty clone is rebuilt by its first pop.
+            heap: BinaryHeap {
+                dirty: self.dirty,
+                ..BinaryHeap::from_raw(self.values.clone())
+            },
+            borrow: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the levels of the tree, from the root down.
+    /// Each level is a slice of the elements; all levels are full except maybe the last one.
+    pub fn levels(&self) -> impl Iterator<Item = &[T]> {
+        let mut start = 0;
+        let mut width: usize = 1;
+        std::iter::from_fn(move || {
+            if start >= self.values.len() {
+                return None;
+            }
+            let end = start.saturating_add(width).min(self.values.len());
+            let level = &self.values[start..end];
+            start = end;
+            width = width.saturating_mul(2);
+            Some(level)
+        })
+    }
+
+    /// Returns the number of elements on each level of the tree, from the root down.
+    pub fn level_sizes(&self) -> Vec<usize> {
+        self.levels().map(<[T]>::len).collect()
+    }
+
+    /// Returns the number of levels of the tree, or 0 if the heap is empty.
+    pub fn height(&self) -> usize {
+        match self.values.len() {
+            0 => 0,
+            len => len.ilog2() as usize + 1,
+        }
+    }
+
+    /// Returns the depth of the node at the given index, where the root has depth 0.
+    /// Panics if the index is out of bounds.
+    pub fn depth_of(&self, index: usize) -> usize {
+        assert!(index < self.values.len(), "index out of bounds");
+        (index + 1).ilog2() as usize
+    }
+
+    /// Returns the tree in the Graphviz DOT format, for debugging.
+    /// Every node is labeled by its value and its index in the heap.
+    pub fn to_dot(&self) -> String
+    where
+        T: Display,
+    {
+        let mut dot = String::from("digraph heap {\n");
+        for (index, value) in self.values.iter().enumerate() {
+            let label = value.to_string().replace('\\', "\\\\").replace('"', "\\\"");
+            dot.push_str(&format!(
+                "    n{} [label=\"{} ({})\"];\n",
+                index, label, index
+            ));
+        }
+        for index in 1..self.values.len() {
+            dot.push_str(&format!("    n{} -> n{};\n", father(index).unwrap(), index));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns an imutable borrow to the smallest element in the heap.
+    /// If the heap is empty, the value returned by f is inserted first.
+    /// Panics on a heap with a capacity ceiling of zero, which can't hold the value.
+    pub fn peek_or_insert_with<F: FnOnce() -> T>(&mut self, f: F) -> &T {
+        self.flush();
+        if self.values.is_empty() {
+            self.push(f());
+        }
+        self.values
+            .first()
+            .expect("the heap has a capacity ceiling of zero")
+    }
+
+    /// Returns imutable borrows to the two smallest elements in the heap, smallest first.
+    /// Return None if the heap has less than two elements.
+    /// The second smallest element is always one of the sons of the root.
+    pub fn top_two(&self) -> Option<(&T, &T)> {
+        if self.dirty {
+            let mut sorted: Vec<&T> = self.values.iter().collect();
+            sorted.sort();
+            return Some((sorted.first()?, sorted.get(1)?));
+        }
+
+        let first = self.values.first()?;
+        let second = match (self.values.get(1), self.values.get(2)) {
+            (Some(left), Some(right)) => left.min(right),
+            (Some(left), None) => left,
+            _ => return None,
+        };
+        Some((first, second))
+    }
+
+    /// Returns the leaves of the tree, which are the last half of the elements.
+    /// The nodes with an index of at least len() / 2 have no sons.
+    pub fn leaves(&self) -> &[T] {
+        &self.values[self.values.len() / 2..]
+    }
+
+    /// Returns an imutable borrow to the largest element in the heap.
+    /// Return None if the heap is empty.
+    /// The largest element is always a leaf, so only the leaves are scanned. This is O(n/2).
+    pub fn max(&self) -> Option<&T> {
+        if self.dirty {
+            return self.values.iter().max();
+        }
+        self.leaves().iter().max()
+    }
+
+    /// Returns ownership of the largest element in the heap and deletes it from the heap.
+    /// Return None if the heap is empty.
+    /// Like max, this scans the leaves in O(n/2).
+    pub fn pop_max(&mut self) -> Option<T> {
+        self.flush();
+        let first_leaf = self.values.len() / 2;
+        let (offset, _) = self
+            .leaves()
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.cmp(b))?;
+        let index = first_leaf + offset;
+
+        // The last element takes the place of the maximum. That place is a leaf,
+        // so the element can only need to move up.
+        let max = self.values.swap_remove(index);
+        if index < self.values.len() {
+            sift_up(&mut self.values, index);
+        }
+        self.maybe_shrink();
+        Some(max)
+    }
+
+    /// Pushes every element of the iterator, and returns a clone of the smallest element
+    /// after each push. The returned minima never increase.
+    pub fn push_all_tracking_min<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.flush();
+        let iter = iter.into_iter();
+        let mut minima = Vec::with_capacity(iter.size_hint().0);
+        for value in iter {
+            self.push(value);
+            minima.extend(self.top().cloned());
+        }
+        minima
+    }
+
+    /// Insert a new value in the heap. If the heap is at its capacity ceiling, the largest
+    /// element is evicted to make room and returned, as long as the new value is smaller
+    /// than it. Otherwise, the new value is returned back without being inserted.
+    /// Evicting scans the leaves of the heap, like max, so it is O(n/2).
+    /// Heaps without a ceiling always insert the value and return None.
+    pub fn push_clamped(&mut self, value: T) -> Option<T> {
+        match self.ceiling {
+            Some(ceiling) => self.push_bounded(value, ceiling),
+            None => {
+                self.push_unclamped(value);
+                None
+            }
+        }
+    }
+
+    /// Insert a new value in the heap if it holds less than max_len elements, and return None.
+    /// Otherwise, if the value is smaller than the largest element, the largest element is
+    /// replaced and returned. If it isn't, the value is returned back without being inserted.
+    /// This keeps the max_len smallest values seen so far. Replacing is O(n/2), like max.
+    /// On a heap with a capacity ceiling, max_len is clamped to the ceiling.
+    pub fn push_bounded(&mut self, value: T, max_len: usize) -> Option<T> {
+        let max_len = max_len.min(self.ceiling.unwrap_or(usize::MAX));
+        if self.values.len() < max_len {
+            self.push_unclamped(value);
+            return None;
+        }
+
+        match self.max() {
+            Some(max) if value < *max => {
+                let evicted = self.pop_max();
+                self.push_unclamped(value);
+                evicted
+            }
+            _ => Some(value),
+        }
+    }
+
+    /// Feeds a value from a stream into the heap, keeping only the k smallest values seen.
+    /// This is push_bounded, dropping whichever value doesn't fit.
+    pub fn feed_bounded(&mut self, value: T, k: usize) {
+        self.push_bounded(value, k);
+    }
+
+    /// Returns clones of the k largest elements in the heap, in descending order.
+    /// The heap is left untouched. All elements are returned if k is at least len().
+    pub fn k_largest(&self, k: usize) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().top_k(k).into_iter().cloned().collect()
+    }
+
+    /// Consumes the heap and returns its elements in ascending order.
+    /// The elements are sorted in place, reusing the heap's allocation.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let mut values = self.into_sorted_vec_desc();
+        values.reverse();
+        values
+    }
+
+    /// Consumes the heap and returns its elements transformed by f, in the ascending order
+    /// of the original elements.
+    pub fn into_sorted_vec_map<U, F: FnMut(T) -> U>(self, f: F) -> Vec<U> {
+        self.into_sorted_vec().into_iter().map(f).collect()
+    }
+
+    /// Consumes the heap and returns its elements in descending order.
+    /// This is the heapsort behind into_sorted_vec, without the final reverse.
+    pub fn into_sorted_vec_desc(mut self) -> Vec<T> {
+        self.sort_desc_in_place();
+        self.values
+    }
+
+    /// Consumes the heap and returns an iterator over its elements, in descending order.
+    /// The elements are sorted in place upfront, in O(n log n); extracting the maximum
+    /// for every element with a leaf scan would cost O(n) each.
+    pub fn into_iter_sorted_desc(self) -> IntoIterSortedDesc<T> {
+        IntoIterSortedDesc {
+            iter: self.into_sorted_vec_desc().into_iter(),
+        }
+    }
+
+    /// Removes all the elements from the heap and appends them to out, in ascending order.
+    /// The elements are sorted in place first, so the heap keeps its capacity.
+    pub fn drain_sorted_into(&mut self, out: &mut Vec<T>) {
+        self.sort_desc_in_place();
+        out.reserve(self.values.len());
+        out.extend(self.values.drain(..).rev());
+    }
+
+    /// Consumes the heap and returns its elements in ascending order, ordering equal
+    /// elements with the tie_break comparator. This makes the order deterministic when
+    /// elements that compare equal can still be told apart.
+    pub fn into_sorted_vec_by<F: FnMut(&T, &T) -> Ordering>(self, mut tie_break: F) -> Vec<T> {
+        let mut values = self.values;
+        let mut cmp = |a: &T, b: &T| a.cmp(b).then_with(|| tie_break(a, b));
+
+        // The heap order doesn't account for ties, so the heap is rebuilt with both orders.
+        sift::heapify(&mut values, &mut cmp);
+        for end in (1..values.len()).rev() {
+            values.swap(0, end);
+            sift::sift_down(&mut values[..end], 0, &mut cmp);
+        }
+        values.reverse();
+        values
+    }
+
+    /// Returns clones of the elements of the heap in ascending order, leaving the heap untouched.
+    pub fn to_sorted_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut values = self.values.clone();
+        values.sort();
+        values
+    }
+
+    /// Returns the number of elements in the heap that are smaller than the given value.
+    /// This scans all the elements, so it is O(n).
+    pub fn count_less_than(&self, value: &T) -> usize {
+        self.values.iter().filter(|v| *v < value).count()
+    }
+
+    /// Returns the position the given value would have among the sorted elements if it
+    /// were inserted before any equal elements, which is the number of smaller elements.
+    /// Like count_less_than, this is O(n).
+    pub fn rank(&self, value: &T) -> usize {
+        self.count_less_than(value)
+    }
+
+    /// Returns the number of distinct elements in the heap.
+    /// This clones and sorts the elements, so it is O(n log n).
+    pub fn distinct_count(&self) -> usize
+    where
+        T: Clone + PartialEq,
+    {
+        let mut values = self.to_sorted_vec();
+        values.dedup();
+        values.len()
+    }
+
+    /// Consumes the heap and returns its distinct elements in ascending order.
+    pub fn into_sorted_dedup_vec(self) -> Vec<T>
+    where
+        T: PartialEq,
+    {
+        let mut values = self.into_sorted_vec();
+        values.dedup();
+        values
+    }
+
+    /// Consumes the heap and returns a heap of the elements transformed by f.
+    /// The new heap is heapified once, in O(n), since f may change the order.
+    pub fn map<U: Ord, F: FnMut(T) -> U>(self, f: F) -> BinaryHeap<U> {
+        BinaryHeap::from_vec(self.values.into_iter().map(f).collect())
+    }
+
+    /// Consumes the heap and returns a heap of the Some results of f, heapified once.
+    pub fn filter_map<U: Ord, F: FnMut(T) -> Option<U>>(self, f: F) -> BinaryHeap<U> {
+        BinaryHeap::from_vec(self.values.into_iter().filter_map(f).collect())
+    }
+
+    /// Consumes the heap and splits it into the elements that match the predicate
+    /// and the ones that don't. Each resulting heap is heapified once.
+    pub fn partition<F: FnMut(&T) -> bool>(self, pred: F) -> (BinaryHeap<T>, BinaryHeap<T>) {
+        let (matching, rest): (Vec<T>, Vec<T>) = self.values.into_iter().partition(pred);
+        (BinaryHeap::from_vec(matching), BinaryHeap::from_vec(rest))
+    }
+
+    /// Pops elements while the smallest one matches the predicate.
+    /// Returns the popped elements in ascending order.
+    pub fn pop_while<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> Vec<T> {
+        let mut popped = vec![];
+        while self.top().is_some_and(&mut pred) {
+            popped.push(self.pop().unwrap());
+        }
+        popped
+    }
+
+    /// Pops at most n elements, stopping early once the smallest one doesn't match the predicate.
+    /// Returns the popped elements in ascending order.
+    pub fn pop_n_while<F: FnMut(&T) -> bool>(&mut self, n: usize, mut pred: F) -> Vec<T> {
+        let mut popped = Vec::with_capacity(n.min(self.values.len()));
+        while popped.len() < n && self.top().is_some_and(&mut pred) {
+            popped.push(self.pop().unwrap());
+        }
+        popped
+    }
+
+    /// Returns the number of elements the heap can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.values.capacity()
+    }
+
+    /// Returns an estimate of the number of bytes used by the heap.
+    /// This counts the backing storage and the heap itself, but not memory
+    /// that the elements own, like the contents of a String.
+    pub fn memory_usage(&self) -> usize {
+        self.values.capacity() * mem::size_of::<T>() + mem::size_of::<Self>()
+    }
+
+    /// Reserves capacity for at least additional more elements.
+    /// Panics if the new capacity overflows, and aborts if the allocation fails.
+    /// Prefer try_reserve when the size comes from untrusted input.
+    /// On a heap with a capacity ceiling, the capacity never grows past the ceiling.
+    pub fn reserve(&mut self, additional: usize) {
+        match self.ceiling {
+            Some(_) => self.values.reserve_exact(self.clamp_additional(additional)),
+            None => self.values.reserve(additional),
+        }
+    }
+
+    /// Tries to reserve capacity for at least additional more elements.
+    /// Returns an error instead of panicking if the capacity overflows or the allocation fails.
+    /// On a heap with a capacity ceiling, the capacity never grows past the ceiling.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        match self.ceiling {
+            Some(_) => self
+                .values
+                .try_reserve_exact(self.clamp_additional(additional)),
+            None => self.values.try_reserve(additional),
+        }
+    }
+
+    /// Removes all the elements from the heap, and makes sure it can then hold
+    /// at least capacity elements without reallocating.
+    /// On a heap with a capacity ceiling, the capacity never grows past the ceiling.
+    pub fn clear_and_reserve(&mut self, capacity: usize) {
+        self.values.clear();
+        self.reserve(capacity);
+    }
+
+    // Lowers a request for additional capacity so that it fits under the ceiling.
+    fn clamp_additional(&self, additional: usize) -> usize {
+        match self.ceiling {
+            Some(ceiling) => additional.min(ceiling.saturating_sub(self.values.len())),
+            None => additional,
+        }
+    }
+
+    /// Keeps only the elements that match the predicate, and returns how many were removed.
+    /// The heap is rebuilt once afterwards, in O(n).
+    pub fn retain_count<F: FnMut(&T) -> bool>(&mut self, f: F) -> usize {
+        let old_len = self.values.len();
+        self.values.retain(f);
+
+        let removed = old_len - self.values.len();
+        if removed > 0 {
+            self.rebuild_after_edit();
+        }
+        removed
+    }
+
+    /// Removes the k largest elements from the heap and returns them as a new heap.
+    /// The elements are partitioned in O(n), and both heaps are rebuilt once.
+    /// All the elements are moved if k is at least len().
+    pub fn split_off_largest(&mut self, k: usize) -> BinaryHeap<T> {
+        if k == 0 {
+            return BinaryHeap::new();
+        }
+        if k >= self.values.len() {
+            self.dirty = false;
+            return BinaryHeap::from_vec(mem::take(&mut self.values));
+        }
+
+        let at = self.values.len() - k;
+        self.values.select_nth_unstable(at);
+        let largest = self.values.split_off(at);
+        self.rebuild();
+        self.maybe_shrink();
+        BinaryHeap::from_vec(largest)
+    }
+
+    /// Appends all the elements of the iterator, then rebuilds the heap once, in O(n + m).
+    /// This beats pushing the elements one by one when many elements are added at once.
+    /// On a heap with a capacity ceiling, the elements are pushed one by one instead.
+    pub fn append_from_iter<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        if self.ceiling.is_some() {
+            self.extend(iter);
+            return;
+        }
+
+        let iter = iter.into_iter();
+        reserve_for(&mut self.values, &iter);
+        self.values.extend(iter);
+        self.rebuild();
+    }
+
+    /// Restores the heap property over all the elements, in O(n).
+    pub fn rebuild(&mut self) {
+        heapify(&mut self.values);
+        self.dirty = false;
+
+        #[cfg(test)]
+        REBUILDS.with(|rebuilds| rebuilds.set(rebuilds.get() + 1));
+    }
+
+    /// Applies f to every element of the heap, then rebuilds the heap once, in O(n).
+    /// This is cheaper than popping and pushing back every element.
+    pub fn map_in_place<F: FnMut(&mut T)>(&mut self, f: F) {
+        self.values.iter_mut().for_each(f);
+        self.rebuild_after_edit();
+    }
+
+    /// Replaces the smallest element with the given value and returns it, without sifting.
+    /// If the heap is empty, the value is pushed like with push_clamped, so a heap with a
+    /// capacity ceiling of 0 returns the value back.
+    ///
+    /// The heap is marked as dirty and rebuilt by the next operation that needs the heap
+    /// order, so that many replacements share a single rebuild.
+    pub fn swap_top(&mut self, value: T) -> Option<T> {
+        match self.values.first_mut() {
+            Some(top) => {
+                self.dirty = true;
+                Some(mem::replace(top, value))
+            }
+            None => self.push_clamped(value),
+        }
+    }
+
+    /// Takes the smallest element by value, replaces it with the result of f and sifts it down.
+    /// Returns false if the heap is empty, in which case f is not called.
+    /// If f panics, the heap stays valid but loses the element that was passed to f.
+    pub fn update_top<F: FnOnce(T) -> T>(&mut self, f: F) -> bool {
+        // Sifts the root down when dropped, even if f panics.
+        struct SiftRootOnDrop<'a, T: Ord>(&'a mut Vec<T>);
+
+        impl<T: Ord> Drop for SiftRootOnDrop<'_, T> {
+            fn drop(&mut self) {
+                sift_down(self.0, 0);
+            }
+        }
+
+        self.flush();
+        let Some(last) = self.values.pop() else {
+            return false;
+        };
+        if self.values.is_empty() {
+            self.values.push(f(last));
+            return true;
+        }
+
+        // The last element fills the root while f runs, so the heap is never left with a gap.
+        let top = mem::replace(&mut self.values[0], last);
+        let guard = SiftRootOnDrop(&mut self.values);
+        let updated = f(top);
+        let last = mem::replace(&mut guard.0[0], updated);
+        guard.0.push(last);
+        true
+    }
+
+    /// Insert a new value in the heap, unless it already holds max_len elements.
+    /// Returns the value back if it was not inserted.
+    /// On a heap with a capacity ceiling, max_len is clamped to the ceiling.
+    pub fn checked_push(&mut self, value: T, max_len: usize) -> Result<(), T> {
+        let max_len = max_len.min(self.ceiling.unwrap_or(usize::MAX));
+        if self.values.len() >= max_len {
+            return Err(value);
+        }
+        self.push_unclamped(value);
+        Ok(())
+    }
+
+    fn push_unclamped(&mut self, value: T) {
+        // Under a capacity ceiling, the capacity doubles like usual, but stops at the ceiling.
+        if let Some(ceiling) = self.ceiling {
+            let capacity = self.values.capacity();
+            if self.values.len() == capacity {
+                let target = capacity.saturating_mul(2).max(4).min(ceiling);
+                self.values.reserve_exact(target - self.values.len());
+            }
+        }
+        self.values.push(value);
+        // A dirty heap is rebuilt as a whole later, so there is no need to sift.
+        if self.dirty {
+            return;
+        }
+        let last = self.values.len() - 1;
+        sift_up(&mut self.values, last);
+    }
+
+    // Sorts the elements in descending order, with an in-place heapsort.
+    // The heap is left invalid, unless it has at most one element.
+    fn sort_desc_in_place(&mut self) {
+        self.flush();
+        // Moving the minimum to the end of the shrinking heap sorts in descending order.
+        for end in (1..self.values.len()).rev() {
+            self.values.swap(0, end);
+            sift_down(&mut self.values[..end], 0);
+        }
+    }
+
+    // Rebuilds the heap after an edit, or defers the rebuild in deferred rebuild mode.
+    fn rebuild_after_edit(&mut self) {
+        if self.deferred_rebuild {
+            self.dirty = true;
+        } else {
+            self.rebuild();
+        }
+    }
+
+    // Rebuilds the heap if an edit was deferred.
+    fn flush(&mut self) {
+        if self.dirty {
+            self.rebuild();
+        }
+    }
+
+    // Shrinks the backing storage if auto-shrink is enabled and most of it is unused.
+    // Zero-sized types never allocate, and their Vec reports a capacity of usize::MAX.
+    fn maybe_shrink(&mut self) {
+        let capacity = self.values.capacity();
+        if self.auto_shrink
+            && mem::size_of::<T>() != 0
+            && capacity > AUTO_SHRINK_MIN_CAPACITY
+            && self.values.len() * 4 < capacity
+        {
+            self.values.shrink_to(self.values.len() * 2);
+        }
+    }
+}
+
+/// Formats the heap as a sorted list, like [1, 2, 3], which doesn't depend on the layout.
+impl<T: Ord + Display> Display for BinaryHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sorted: Vec<&T> = self.values.iter().collect();
+        sorted.sort();
+
+        write!(f, "[")?;
+        for (i, value) in sorted.into_iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            value.fmt(f)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Formats the elements in heap order, for debugging the layout.
+impl<T: Ord + Debug> Debug for BinaryHeap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.values).finish()
+    }
+}
+
+/// The error returned when a heap would hold more elements than allowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityError {
+    max: usize,
+}
+
+impl CapacityError {
+    /// Returns the maximum number of elements that was exceeded.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+}
+
+impl Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "more than {} elements", self.max)
+    }
+}
+
+impl Error for CapacityError {}
+
+impl<T: Ord> Default for BinaryHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Reserves room for the elements the iterator is about to yield.
+// When the size hint is exact, exactly that much is reserved, so one-shot
+// builds don't over-allocate. Otherwise the lower bound is reserved as usual.
+fn reserve_for<T, I: Iterator<Item = T>>(values: &mut Vec<T>, iter: &I) {
+    match iter.size_hint() {
+        (lower, Some(upper)) if lower == upper => values.reserve_exact(lower),
+        (lower, _) => values.reserve(lower),
+    }
 }
-fn right_son(index: usize) -> usize {
-    2 * index + 2
+
+impl<T: Ord> FromIterator<T> for BinaryHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut values = Vec::new();
+        reserve_for(&mut values, &iter);
+        values.extend(iter);
+        BinaryHeap::from_vec(values)
+    }
 }
-fn father(index: usize) -> Option<usize> {
-    if index == 0 {
-        None
-    } else {
-        Some((index + 1) / 2 - 1)
+
+impl<T: Ord> Extend<T> for BinaryHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        // A heap with a capacity ceiling must evict as it goes, and must not grow.
+        if self.ceiling.is_some() {
+            for value in iter {
+                self.push(value);
+            }
+            return;
+        }
+        reserve_for(&mut self.values, &iter);
+
+        // An empty heap can be built in one pass instead of pushing every value.
+        if self.values.is_empty() {
+            self.values.extend(iter);
+            heapify(&mut self.values);
+            return;
+        }
+
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+/// An owning iterator over the elements of a heap, in descending order.
+/// Created by BinaryHeap::into_iter_sorted_desc.
+pub struct IntoIterSortedDesc<T> {
+    iter: std::vec::IntoIter<T>,
+}
+
+impl<T> Iterator for IntoIterSortedDesc<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIterSortedDesc<T> {}
+
+impl<T> FusedIterator for IntoIterSortedDesc<T> {}
+
+/// A read-only view of a heap. It only exposes queries, so it can be handed out
+/// without allowing any changes to the heap. Created by BinaryHeap::view.
+pub struct HeapView<'a, T: Ord> {
+    heap: &'a BinaryHeap<T>,
+}
+
+impl<'a, T: Ord> HeapView<'a, T> {
+    /// Returns an imutable borrow to the smallest element in the heap.
+    /// Return None if the heap is empty.
+    pub fn top(&self) -> Option<&'a T> {
+        self.heap.top()
+    }
+
+    /// Returns the number of elements in the heap.
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Returns true if the heap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns an iterator over the elements of the heap, in no particular order.
+    pub fn iter(&self) -> slice::Iter<'a, T> {
+        self.heap.iter()
+    }
+
+    /// Returns the elements of the heap in their heap order.
+    /// While a rebuild is pending, after swap_top or an edit in deferred rebuild mode,
+    /// the order of the elements is unspecified.
+    pub fn as_slice(&self) -> &'a [T] {
+        &self.heap.values
+    }
+}
+
+impl<T: Ord> Clone for HeapView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Ord> Copy for HeapView<'_, T> {}
+
+/// An iterator over clones of the elements of a heap, in ascending order.
+/// Created by BinaryHeap::iter_sorted.
+pub struct IterSorted<'a, T: Ord> {
+    heap: BinaryHeap<T>,
+    borrow: PhantomData<&'a BinaryHeap<T>>,
+}
+
+impl<T: Ord> Iterator for IterSorted<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.heap.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+impl<T: Ord> ExactSizeIterator for IterSorted<'_, T> {}
+
+impl<T: Ord> FusedIterator for IterSorted<'_, T> {}
+
+/// Iterates over the elements of the heap, in no particular order.
+impl<'a, T: Ord> IntoIterator for &'a BinaryHeap<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Ord> MinHeap<T> for BinaryHeap<T> {
+    // On a heap with a capacity ceiling, the evicted element is dropped.
+    fn push(&mut self, val: T) {
+        self.push_clamped(val);
+    }
+
+    fn top(&self) -> Option<&T> {
+        if self.dirty {
+            return self.values.iter().min();
+        }
+        self.values.first()
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.flush();
+        // Move the last element into the root instead of swapping it there,
+        // which saves a full copy of the element.
+        let mut top = self.values.pop()?;
+        if !self.values.is_empty() {
+            top = mem::replace(&mut self.values[0], top);
+            sift_down(&mut self.values, 0);
+        }
+        self.maybe_shrink();
+
+        Some(top)
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use rand::distributions::Standard;
+    use rand::prelude::*;
+    use std::cell::Cell;
+    use std::cmp::{Ordering, Reverse};
+    use std::collections;
+    use std::panic::{self, AssertUnwindSafe};
+
+    enum Operation<T> {
+        Insert(T),
+        Pop(),
+    }
+
+    #[test]
+    fn empty_heap_is_empty() {
+        let h = BinaryHeap::<usize>::new();
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn nonempty_heap_is_not_empty() {
+        let mut h = BinaryHeap::<usize>::new();
+        h.push(10);
+        assert!(!h.is_empty());
+    }
+
+    #[test]
+    fn empty_heap_has_zero_len() {
+        let h = BinaryHeap::<usize>::new();
+        assert_eq!(h.len(), 0);
+    }
+
+    #[test]
+    fn insert_increases_heap_len() {
+        let mut h = BinaryHeap::<usize>::new();
+        h.push(2);
+        h.push(10);
+        h.push(4);
+        h.push(100);
+        assert_eq!(h.len(), 4);
+    }
+
+    #[test]
+    fn pop_decreases_heap_len() {
+        let mut h = BinaryHeap::<usize>::new();
+        h.push(2);
+        h.push(10);
+        h.push(4);
+        h.push(100);
+
+        for _ in 0..4 {
+            h.pop();
+        }
+        assert_eq!(h.len(), 0);
+    }
+
+    #[test]
+    fn top_returns_none_on_empty_heap() {
+        let h = BinaryHeap::<usize>::new();
+        assert!(h.top().is_none());
+    }
+
+    #[test]
+    fn top_returns_minimum_element() {
+        let mut h = BinaryHeap::new();
+        let values = vec![1, 10, 3, -4123, 34, 100, 124];
+        for v in values {
+            h.push(v);
+        }
+        assert_eq!(*h.top().unwrap(), -4123);
+    }
+
+    #[test]
+    fn top_changes_on_pop() {
+        let mut h = BinaryHeap::new();
+        let values = vec![1, 10, 3, -4123, 34, 100, 124];
+
+        let mut ordered = values.clone();
+        ordered.sort();
+
+        for v in values.iter() {
+            h.push(*v);
+        }
+
+        for i in 0..values.len() - 1 {
+            h.pop();
+            assert_eq!(*h.top().unwrap(), ordered[i + 1]);
+        }
+    }
+
+    #[test]
+    fn pop_returns_none_on_empty_heap() {
+        let mut h = BinaryHeap::<usize>::new();
+        assert!(h.pop().is_none())
+    }
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn pop_returns_and_erases_minimum_element() {
+        let mut h = BinaryHeap::new();
+        let values = vec![1, 10, 3, -4123, 34, 100, 124];
+
+        let mut ordered = values.clone();
+        ordered.sort();
+
+        for v in values.iter() {
+            h.push(*v);
+        }
+
+        for i in 0..values.len() {
+            assert_eq!(h.pop().unwrap(), ordered[i]);
+        }
+    }
+
+    // The operation gen strategy.
+    fn strategy<T>(rng: &mut ThreadRng, heap_size: usize) -> Operation<T>
+    where
+        Standard: Distribution<T>,
+    {
+        if heap_size > 0 {
+            let should_pop = rng.gen_bool(0.3);
+            if should_pop {
+                return Operation::Pop();
+            }
+        }
+        Operation::Insert(rng.gen())
+    }
+
+    // Compares our heap to the standard library one on random operations.
+    fn fuzz_against_std(num_operations: usize) {
+        let mut rng = thread_rng();
+
+        let mut ours = BinaryHeap::<i32>::new();
+        let mut theirs = collections::BinaryHeap::<Reverse<i32>>::new();
+
+        for _ in 0..num_operations {
+            let op = strategy(&mut rng, ours.len());
+            match op {
+                Operation::Insert(val) => {
+                    ours.push(val);
+                    theirs.push(Reverse(val));
+                }
+                Operation::Pop() => {
+                    assert_eq!(ours.pop().unwrap(), theirs.pop().unwrap().0);
+                }
+            }
+        }
+    }
+
+    // Random fuzz testing.
+    #[test]
+    #[ignore]
+    fn fuzz() {
+        fuzz_against_std(5000000);
+    }
+
+    #[test]
+    fn small_fuzz() {
+        fuzz_against_std(20000);
+    }
+
+    // Exercises the unsafe sift loops with a type that owns heap memory, so that
+    // double drops or leaks are caught by Miri (cargo +nightly miri test).
+    #[test]
+    fn fuzz_with_owned_values() {
+        let mut rng = thread_rng();
+        let mut ours = BinaryHeap::<String>::new();
+        let mut theirs = collections::BinaryHeap::<Reverse<String>>::new();
+
+        for _ in 0..2000 {
+            if !ours.is_empty() && rng.gen_bool(0.3) {
+                assert_eq!(ours.pop().unwrap(), theirs.pop().unwrap().0);
+            } else {
+                let val = rng.gen_range(0..500).to_string();
+                ours.push(val.clone());
+                theirs.push(Reverse(val));
+            }
+        }
+    }
+
+    #[test]
+    fn push_sifts_up_through_many_levels() {
+        let mut h = BinaryHeap::new();
+        for v in (0..1000).rev() {
+            h.push(v);
+            assert_eq!(*h.top().unwrap(), v);
+        }
+        for expected in 0..1000 {
+            assert_eq!(h.pop().unwrap(), expected);
+        }
+    }
+
+    // Checks that every node is not greater than its children.
+    fn is_valid<T: Ord>(h: &BinaryHeap<T>) -> bool {
+        (1..h.values.len()).all(|i| h.values[father(i).unwrap()] <= h.values[i])
+    }
+
+    #[test]
+    fn pop_keeps_heap_valid() {
+        let mut rng = thread_rng();
+        let mut h = BinaryHeap::new();
+        for _ in 0..1000 {
+            h.push(rng.gen_range(-100..100));
+        }
+
+        while !h.is_empty() {
+            h.pop();
+            assert!(is_valid(&h));
+        }
+    }
+
+    #[test]
+    fn from_vec_of_large_shuffled_input_is_valid() {
+        let mut rng = thread_rng();
+        let mut values: Vec<i32> = (0..100000).collect();
+        values.shuffle(&mut rng);
+
+        let mut h = BinaryHeap::from_vec(values);
+        assert_eq!(h.len(), 100000);
+        assert!(is_valid(&h));
+        for expected in 0..100000 {
+            assert_eq!(h.pop().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn collect_builds_valid_heap() {
+        let h: BinaryHeap<i32> = vec![5, 3, 8, -1, 0, 7, 3].into_iter().collect();
+        assert_eq!(h.len(), 7);
+        assert_eq!(*h.top().unwrap(), -1);
+        assert!(is_valid(&h));
+    }
+
+    #[test]
+    fn extend_empty_and_nonempty_heap() {
+        let mut h = BinaryHeap::new();
+        h.extend(vec![10, 4, 6]);
+        assert!(is_valid(&h));
+
+        h.extend(vec![5, 1, 12]);
+        assert!(is_valid(&h));
+
+        let popped: Vec<i32> = std::iter::from_fn(|| h.pop()).collect();
+        assert_eq!(popped, vec![1, 4, 5, 6, 10, 12]);
+    }
+
+    #[test]
+    fn auto_shrink_releases_memory_when_drained() {
+        let mut h = BinaryHeap::with_auto_shrink(true);
+        for v in 0..10000 {
+            h.push(v);
+        }
+        let peak = h.capacity();
+
+        while h.len() > 10 {
+            h.pop();
+            assert!(h.len() * 4 >= h.capacity() || h.capacity() <= AUTO_SHRINK_MIN_CAPACITY);
+        }
+        assert!(h.capacity() < peak);
+        assert!(is_valid(&h));
+    }
+
+    #[test]
+    fn default_heap_keeps_capacity_when_drained() {
+        let mut h = BinaryHeap::new();
+        for v in 0..10000 {
+            h.push(v);
+        }
+        let peak = h.capacity();
+
+        while h.pop().is_some() {}
+        assert_eq!(h.capacity(), peak);
+    }
+
+    #[test]
+    fn collect_from_exact_size_iterator_does_not_overallocate() {
+        let values: Vec<i32> = (0..1000).rev().collect();
+        let h: BinaryHeap<i32> = values.into_iter().collect();
+        assert_eq!(h.len(), 1000);
+        assert_eq!(h.capacity(), 1000);
+    }
+
+    #[test]
+    fn extend_from_exact_size_iterator_reserves_once() {
+        let mut h = BinaryHeap::from_vec(vec![3, 2, 1]);
+        assert_eq!(h.capacity(), 3);
+
+        let values: Vec<i32> = (0..1000).collect();
+        h.extend(values);
+
+        // Growing by doubling would have left spare capacity behind.
+        assert_eq!(h.len(), 1003);
+        assert_eq!(h.capacity(), 1003);
+        assert!(is_valid(&h));
+    }
+
+    // A large element, that is expensive to move.
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct Large {
+        key: i32,
+        payload: [u64; 32],
+    }
+
+    #[test]
+    fn large_elements_pop_in_order() {
+        let mut rng = thread_rng();
+        let mut h = BinaryHeap::new();
+        let mut keys = vec![];
+        for i in 0..1000 {
+            let key = rng.gen_range(-500..500);
+            keys.push(key);
+            h.push(Large {
+                key,
+                payload: [i; 32],
+            });
+        }
+        keys.sort();
+
+        for expected in keys {
+            let value = h.pop().unwrap();
+            assert_eq!(value.key, expected);
+            assert!(value.payload.iter().all(|&p| p == value.payload[0]));
+        }
+        assert!(h.is_empty());
+    }
+
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct Zst;
+
+    #[test]
+    fn zero_sized_values() {
+        for mut h in [BinaryHeap::new(), BinaryHeap::with_auto_shrink(true)] {
+            for i in 0..10000 {
+                assert_eq!(h.len(), i);
+                h.push(Zst);
+            }
+            assert_eq!(h.len(), 10000);
+            assert!(h.top().is_some());
+
+            for i in (0..10000).rev() {
+                assert!(h.pop().is_some());
+                assert_eq!(h.len(), i);
+            }
+            assert!(h.pop().is_none());
+        }
+
+        let h = BinaryHeap::from_vec(vec![(); 10000]);
+        assert_eq!(h.len(), 10000);
+    }
+
+    thread_local! {
+        static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+        static PANIC_AT: Cell<usize> = const { Cell::new(usize::MAX) };
+        static DROPS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // Sets up the next comparison that will panic, counting from now.
+    fn panic_on_comparison(n: usize) {
+        COMPARISONS.with(|c| c.set(0));
+        PANIC_AT.with(|p| p.set(n));
+    }
+
+    // A value whose comparison panics when the counter hits PANIC_AT.
+    // Drops are counted in DROPS.
+    #[derive(PartialEq, Eq, Debug)]
+    struct Fragile(i32);
+
+    impl Drop for Fragile {
+        fn drop(&mut self) {
+            DROPS.with(|d| d.set(d.get() + 1));
+        }
+    }
+
+    impl PartialOrd for Fragile {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Fragile {
+        fn cmp(&self, other: &Self) -> Ordering {
+            let count = COMPARISONS.with(|c| {
+                c.set(c.get() + 1);
+                c.get()
+            });
+            if count == PANIC_AT.with(|p| p.get()) {
+                panic!("comparison {} panics", count);
+            }
+            self.0.cmp(&other.0)
+        }
+    }
+
+    // Returns the values in the heap, sorted.
+    fn sorted_values(h: &BinaryHeap<Fragile>) -> Vec<i32> {
+        let mut values: Vec<i32> = h.values.iter().map(|f| f.0).collect();
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn push_is_panic_safe() {
+        let mut h = BinaryHeap::new();
+        panic_on_comparison(3);
+
+        for v in [10, 9, 8] {
+            h.push(Fragile(v));
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(|| h.push(Fragile(7))));
+        assert!(result.is_err());
+
+        // The pushed value is neither lost nor duplicated.
+        panic_on_comparison(usize::MAX);
+        assert_eq!(h.len(), 4);
+        assert_eq!(sorted_values(&h), vec![7, 8, 9, 10]);
+
+        // A panicking push in the middle of a longer sift.
+        for v in [6, 5, 4, 3] {
+            h.push(Fragile(v));
+        }
+        panic_on_comparison(2);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| h.push(Fragile(0))));
+        assert!(result.is_err());
+
+        panic_on_comparison(usize::MAX);
+        assert_eq!(sorted_values(&h), vec![0, 3, 4, 5, 6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn from_vec_is_panic_safe() {
+        for n in [1, 10, 50, 120] {
+            let values: Vec<Fragile> = (0..100).rev().map(Fragile).collect();
+            DROPS.with(|d| d.set(0));
+            panic_on_comparison(n);
+
+            let result = panic::catch_unwind(AssertUnwindSafe(|| BinaryHeap::from_vec(values)));
+            assert!(result.is_err());
+
+            // Every value was dropped exactly once while unwinding.
+            panic_on_comparison(usize::MAX);
+            assert_eq!(DROPS.with(|d| d.get()), 100);
+        }
+    }
+
+    #[test]
+    fn pop_keeps_capacity() {
+        let mut h = BinaryHeap::from_vec(Vec::with_capacity(100));
+        for v in 0..100 {
+            h.push(v);
+        }
+        assert!(h.capacity() >= 100);
+
+        while h.pop().is_some() {
+            assert!(h.capacity() >= 100);
+        }
+        assert!(h.is_empty());
+        assert!(h.capacity() >= 100);
+    }
+
+    // A value whose comparison always returns the same ordering.
+    #[derive(PartialEq, Eq)]
+    struct Broken(Ordering);
+
+    impl PartialOrd for Broken {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Broken {
+        fn cmp(&self, _: &Self) -> Ordering {
+            self.0
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "inconsistent Ord implementation")]
+    fn non_total_ord_is_detected_on_push() {
+        let mut h = BinaryHeap::new();
+        h.push(Broken(Ordering::Greater));
+        h.push(Broken(Ordering::Greater));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "inconsistent Ord implementation")]
+    fn non_total_ord_is_detected_on_pop() {
+        let mut h = BinaryHeap::new();
+        for _ in 0..3 {
+            h.values.push(Broken(Ordering::Less));
+        }
+        h.pop();
+    }
+
+    #[test]
+    fn reserve_increases_capacity() {
+        let mut h = BinaryHeap::<i32>::new();
+        h.reserve(50);
+        assert!(h.capacity() >= 50);
+        assert!(h.try_reserve(100).is_ok());
+        assert!(h.capacity() >= 100);
+    }
+
+    #[test]
+    fn try_reserve_reports_overflow() {
+        let mut h = BinaryHeap::from_vec(vec![1u64]);
+        assert!(h.try_reserve(usize::MAX).is_err());
+        assert_eq!(h.len(), 1);
+    }
+
+    #[test]
+    fn checked_push_accepts_below_limit() {
+        let mut h = BinaryHeap::new();
+        assert_eq!(h.checked_push(5, 2), Ok(()));
+        assert_eq!(h.checked_push(3, 2), Ok(()));
+        assert_eq!(h.len(), 2);
+        assert_eq!(*h.top().unwrap(), 3);
+    }
+
+    #[test]
+    fn checked_push_rejects_at_limit() {
+        let mut h = BinaryHeap::from_vec(vec![1, 2]);
+        assert_eq!(h.checked_push(0, 2), Err(0));
+        assert_eq!(h.len(), 2);
+        assert_eq!(*h.top().unwrap(), 1);
+        assert_eq!(BinaryHeap::new().checked_push(7, 0), Err(7));
+    }
+
+    #[test]
+    fn from_vec_validated_accepts_valid_heap() {
+        let values = vec![1, 3, 2, 7, 4, 5];
+        let mut h = BinaryHeap::from_vec_validated(values).unwrap();
+        assert_eq!(h.len(), 6);
+        let popped: Vec<i32> = std::iter::from_fn(|| h.pop()).collect();
+        assert_eq!(popped, vec![1, 2, 3, 4, 5, 7]);
+
+        assert!(BinaryHeap::<i32>::from_vec_validated(vec![]).is_ok());
+    }
+
+    #[test]
+    fn from_vec_validated_returns_invalid_values_back() {
+        let values = vec![1, 3, 2, 0];
+        assert_eq!(
+            BinaryHeap::from_vec_validated(values).err(),
+            Some(vec![1, 3, 2, 0])
+        );
+    }
+
+    #[test]
+    fn from_vec_unchecked_keeps_layout() {
+        let h = unsafe { BinaryHeap::from_vec_unchecked(vec![1, 3, 2, 7, 4]) };
+        assert_eq!(h.values, vec![1, 3, 2, 7, 4]);
+        assert!(is_valid(&h));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn from_vec_unchecked_checks_in_debug() {
+        unsafe { BinaryHeap::from_vec_unchecked(vec![5, 1]) };
+    }
+
+    #[test]
+    fn extend_from_exact_size_iterator_reserves_exactly() {
+        let mut h = BinaryHeap::from_vec(vec![3, 2, 1]);
+        h.extend(vec![5, 4]);
+        assert_eq!(h.capacity(), 5);
+
+        let mut h = BinaryHeap::new();
+        h.extend((0..37).rev());
+        assert_eq!(h.capacity(), 37);
+        assert!(is_valid(&h));
+    }
+
+    #[test]
+    fn iterate_by_reference() {
+        let mut h = BinaryHeap::from_vec(vec![4, 8, 1, 9, 3]);
+        let mut sum = 0;
+        for x in &h {
+            sum += x;
+        }
+        assert_eq!(sum, 25);
+        assert_eq!(h.iter().count(), 5);
+
+        h.push(0);
+        assert_eq!(h.pop(), Some(0));
+        assert_eq!(h.pop(), Some(1));
+    }
+
+    #[test]
+    fn max_returns_none_on_empty_heap() {
+        let h = BinaryHeap::<i32>::new();
+        assert!(h.max().is_none());
+    }
+
+    #[test]
+    fn max_matches_full_scan() {
+        let mut rng = thread_rng();
+        for len in 1..200 {
+            let values: Vec<i32> = (0..len).map(|_| rng.gen_range(-1000..1000)).collect();
+            let h = BinaryHeap::from_vec(values);
+            assert_eq!(h.max(), h.iter().max());
+        }
+    }
+
+    #[test]
+    fn k_largest_matches_sorted_suffix() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..300).map(|_| rng.gen_range(-100..100)).collect();
+        let h = BinaryHeap::from_vec(values.clone());
+
+        let mut descending = values;
+        descending.sort_by(|a, b| b.cmp(a));
+
+        for k in [0, 1, 5, 50, 299, 300, 1000] {
+            let expected = &descending[..k.min(descending.len())];
+            assert_eq!(h.k_largest(k), expected);
+        }
+        assert_eq!(h.len(), 300);
+        assert!(is_valid(&h));
+    }
+
+    #[test]
+    fn clear_and_reserve_empties_and_grows() {
+        let mut h = BinaryHeap::from_vec(vec![5, 2, 9]);
+        h.clear_and_reserve(1000);
+        assert_eq!(h.len(), 0);
+        assert!(h.capacity() >= 1000);
+
+        // Asking for less than the current capacity keeps it.
+        h.clear_and_reserve(10);
+        assert!(h.capacity() >= 1000);
+        assert!(h.pop().is_none());
+    }
+
+    #[test]
+    fn meld_all_combines_every_heap() {
+        let mut rng = thread_rng();
+        let mut all = vec![];
+        let mut heaps = vec![];
+        for _ in 0..5 {
+            let values: Vec<i32> = (0..rng.gen_range(0..100))
+                .map(|_| rng.gen_range(-100..100))
+                .collect();
+            all.extend(values.iter().copied());
+            heaps.push(BinaryHeap::from_vec(values));
+        }
+        all.sort();
+
+        let mut h = BinaryHeap::meld_all(heaps);
+        assert_eq!(h.len(), all.len());
+        assert_eq!(h.capacity(), all.len());
+        let popped: Vec<i32> = std::iter::from_fn(|| h.pop()).collect();
+        assert_eq!(popped, all);
+    }
+
+    #[test]
+    fn into_sorted_vec_is_ascending() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..500).map(|_| rng.gen_range(-100..100)).collect();
+        let mut expected = values.clone();
+        expected.sort();
+
+        assert_eq!(BinaryHeap::from_vec(values).into_sorted_vec(), expected);
+        assert!(BinaryHeap::<i32>::new().into_sorted_vec().is_empty());
+    }
+
+    #[test]
+    fn into_sorted_dedup_vec_is_sorted_and_unique() {
+        let h = BinaryHeap::from_vec(vec![5, 1, 3, 5, 1, 1, 9, 3, 0, 9]);
+        assert_eq!(h.into_sorted_dedup_vec(), vec![0, 1, 3, 5, 9]);
+    }
+
+    #[test]
+    fn to_sorted_vec_does_not_consume() {
+        let mut h = BinaryHeap::from_vec(vec![7, 3, 9, 1, 4]);
+        let layout = h.values.clone();
+
+        assert_eq!(h.to_sorted_vec(), vec![1, 3, 4, 7, 9]);
+        assert_eq!(h.values, layout);
+
+        let popped: Vec<i32> = std::iter::from_fn(|| h.pop()).collect();
+        assert_eq!(popped, vec![1, 3, 4, 7, 9]);
+    }
+
+    #[test]
+    fn map_reorders_elements() {
+        let h = BinaryHeap::from_vec(vec![3, -8, 12, 5, 0]);
+        let mut negated = h.map(|x| -x);
+        assert!(is_valid(&negated));
+        assert_eq!(negated.pop(), Some(-12));
+        assert_eq!(negated.len(), 4);
+
+        let strings = BinaryHeap::from_vec(vec![10, 9, 100]).map(|x| x.to_string());
+        assert_eq!(strings.into_sorted_vec(), vec!["10", "100", "9"]);
+    }
+
+    #[test]
+    fn filter_map_drops_and_transforms() {
+        let h = BinaryHeap::from_vec(vec![4, -1, 7, -9, 0, 3, -2]);
+        let doubled = h.filter_map(|x| if x < 0 { None } else { Some(x * 2) });
+        assert!(is_valid(&doubled));
+        assert_eq!(doubled.into_sorted_vec(), vec![0, 6, 8, 14]);
+    }
+
+    #[test]
+    fn partition_by_parity() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..300).map(|_| rng.gen_range(-100..100)).collect();
+        let mut expected = values.clone();
+        expected.sort();
+
+        let (mut even, mut odd) = BinaryHeap::from_vec(values).partition(|x| x % 2 == 0);
+        let even: Vec<i32> = std::iter::from_fn(|| even.pop()).collect();
+        let odd: Vec<i32> = std::iter::from_fn(|| odd.pop()).collect();
+        assert!(even.windows(2).all(|w| w[0] <= w[1]));
+        assert!(odd.windows(2).all(|w| w[0] <= w[1]));
+        assert!(even.iter().all(|x| x % 2 == 0));
+        assert!(odd.iter().all(|x| x % 2 != 0));
+
+        let mut union = [even, odd].concat();
+        union.sort();
+        assert_eq!(union, expected);
+    }
+
+    #[test]
+    fn pop_while_drains_matching_prefix() {
+        let mut h = BinaryHeap::from_vec(vec![15, 3, 8, 1, 20, 10, 5]);
+        assert_eq!(h.pop_while(|&t| t <= 8), vec![1, 3, 5, 8]);
+        assert_eq!(h.len(), 3);
+        assert!(is_valid(&h));
+        assert_eq!(h.into_sorted_vec(), vec![10, 15, 20]);
+    }
+
+    #[test]
+    fn pop_while_stops_at_first_failure() {
+        let mut h = BinaryHeap::from_vec(vec![2, 4, 5, 6]);
+        assert!(h.pop_while(|&t| t % 2 == 1).is_empty());
+        assert_eq!(h.pop_while(|&t| t % 2 == 0), vec![2, 4]);
+        assert_eq!(h.pop_while(|_| true), vec![5, 6]);
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn levels_of_empty_heap() {
+        let h = BinaryHeap::<i32>::new();
+        assert_eq!(h.levels().count(), 0);
+    }
+
+    #[test]
+    fn levels_follow_tree_boundaries() {
+        let h = BinaryHeap::from_vec((0..7).collect());
+        let levels: Vec<&[i32]> = h.levels().collect();
+        assert_eq!(levels.len(), 3);
+        assert_eq!(levels[0], &h.values[0..1]);
+        assert_eq!(levels[1], &h.values[1..3]);
+        assert_eq!(levels[2], &h.values[3..7]);
+
+        let h = BinaryHeap::from_vec((0..9).collect());
+        let sizes: Vec<usize> = h.levels().map(<[i32]>::len).collect();
+        assert_eq!(sizes, vec![1, 2, 4, 2]);
+    }
+
+    #[test]
+    fn height_for_various_sizes() {
+        for (len, height) in [(0, 0), (1, 1), (2, 2), (3, 2), (7, 3), (8, 4)] {
+            let h = BinaryHeap::from_vec((0..len).collect());
+            assert_eq!(h.height(), height);
+            assert_eq!(h.height(), h.levels().count());
+        }
+    }
+
+    #[test]
+    fn depth_of_matches_levels() {
+        let h = BinaryHeap::from_vec((0..8).collect());
+        let depths: Vec<usize> = (0..8).map(|i| h.depth_of(i)).collect();
+        assert_eq!(depths, vec![0, 1, 1, 2, 2, 2, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn depth_of_out_of_bounds_panics() {
+        BinaryHeap::from_vec(vec![1, 2, 3]).depth_of(3);
+    }
+
+    #[test]
+    fn to_dot_has_an_edge_per_son() {
+        let h = BinaryHeap::from_vec(vec![8, 3, 5, 1, 9, 2]);
+        let dot = h.to_dot();
+        assert!(dot.starts_with("digraph heap {"));
+        assert!(dot.trim_end().ends_with('}'));
+        assert_eq!(dot.matches("->").count(), 5);
+        assert!(dot.contains("n0 [label=\"1 (0)\"];"));
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.contains("n2 -> n5;"));
+    }
+
+    #[test]
+    fn to_dot_escapes_labels() {
+        let h = BinaryHeap::from_vec(vec![String::from("say \"hi\"")]);
+        assert!(h.to_dot().contains("n0 [label=\"say \\\"hi\\\" (0)\"];"));
+    }
+
+    #[test]
+    fn memory_usage_grows_with_capacity() {
+        let mut h = BinaryHeap::<u64>::new();
+        let empty = h.memory_usage();
+        assert_eq!(empty, mem::size_of::<BinaryHeap<u64>>());
+
+        h.reserve(100);
+        assert!(h.memory_usage() >= empty + 100 * 8);
+        assert_eq!(h.memory_usage(), empty + h.capacity() * 8);
+    }
+
+    #[test]
+    fn from_iters_combines_sources() {
+        let mut h = BinaryHeap::from_iters(vec![0..5, 20..23, 3..8]);
+        assert_eq!(h.len(), 13);
+        assert_eq!(h.capacity(), 13);
+
+        let popped: Vec<i32> = std::iter::from_fn(|| h.pop()).collect();
+        assert_eq!(popped, vec![0, 1, 2, 3, 3, 4, 4, 5, 6, 7, 20, 21, 22]);
+    }
+
+    #[test]
+    fn retain_count_reports_removed_elements() {
+        let mut h = BinaryHeap::from_vec((0..50).rev().collect());
+        let old_len = h.len();
+
+        let removed = h.retain_count(|x| x % 3 == 0);
+        assert_eq!(removed, old_len - h.len());
+        assert_eq!(removed, 33);
+        assert!(is_valid(&h));
+        assert_eq!(
+            h.into_sorted_vec(),
+            (0..50).step_by(3).collect::<Vec<i32>>()
+        );
+    }
+
+    #[test]
+    fn retain_count_of_everything_removes_nothing() {
+        let mut h = BinaryHeap::from_vec(vec![3, 1, 2]);
+        assert_eq!(h.retain_count(|_| true), 0);
+        assert_eq!(h.len(), 3);
+        assert_eq!(h.retain_count(|_| false), 3);
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn top_two_needs_two_elements() {
+        assert!(BinaryHeap::<i32>::new().top_two().is_none());
+        assert!(BinaryHeap::from_vec(vec![4]).top_two().is_none());
+        assert_eq!(BinaryHeap::from_vec(vec![9, 4]).top_two(), Some((&4, &9)));
+    }
+
+    #[test]
+    fn top_two_returns_the_two_smallest() {
+        let mut rng = thread_rng();
+        for len in 2..100 {
+            let values: Vec<i32> = (0..len).map(|_| rng.gen_range(-50..50)).collect();
+            let mut ordered = values.clone();
+            ordered.sort();
+
+            let h = BinaryHeap::from_vec(values);
+            assert_eq!(h.top_two(), Some((&ordered[0], &ordered[1])));
+        }
+    }
+
+    #[test]
+    fn distinct_count_of_multiset() {
+        assert_eq!(BinaryHeap::<i32>::new().distinct_count(), 0);
+
+        let h = BinaryHeap::from_vec(vec![4, 1, 4, 4, 2, 1, 9, 0, 9, 9, 9]);
+        assert_eq!(h.distinct_count(), 5);
+        assert_eq!(h.len(), 11);
+    }
+
+    #[test]
+    fn level_sizes_of_ten_elements() {
+        let h = BinaryHeap::from_vec((0..10).collect());
+        assert_eq!(h.level_sizes(), vec![1, 2, 4, 3]);
+        assert!(BinaryHeap::<i32>::new().level_sizes().is_empty());
+    }
+
+    #[test]
+    fn swap_top_then_rebuild() {
+        let mut h = BinaryHeap::from_vec(vec![5, 1, 8, 3, 2]);
+        assert_eq!(h.swap_top(10), Some(1));
+        assert_eq!(h.values[0], 10);
+
+        h.rebuild();
+        assert!(is_valid(&h));
+        assert_eq!(h.into_sorted_vec(), vec![2, 3, 5, 8, 10]);
+    }
+
+    #[test]
+    fn swap_top_then_pop_without_rebuild() {
+        let mut h = BinaryHeap::from_vec(vec![5, 1, 8, 3, 2]);
+        assert_eq!(h.swap_top(10), Some(1));
+        assert_eq!(h.swap_top(0), Some(10));
+        assert_eq!(h.swap_top(9), Some(0));
+
+        assert_eq!(h.top(), Some(&2));
+        assert_eq!(h.max(), Some(&9));
+        assert_eq!(h.pop(), Some(2));
+        assert!(is_valid(&h));
+        h.push(4);
+        assert_eq!(h.into_sorted_vec(), vec![3, 4, 5, 8, 9]);
+    }
+
+    #[test]
+    fn swap_top_on_empty_heap_inserts() {
+        let mut h = BinaryHeap::new();
+        assert_eq!(h.swap_top(4), None);
+        assert_eq!(h.len(), 1);
+        assert_eq!(h.top(), Some(&4));
+    }
+
+    #[test]
+    fn sorts_correctly() {
+        let mut rng = thread_rng();
+        let input: Vec<i32> = (0..1000).map(|_| rng.gen_range(-100..100)).collect();
+        testing::assert_sorts_correctly(BinaryHeap::new(), &input);
+        testing::assert_sorts_correctly(BinaryHeap::with_auto_shrink(true), &input);
+        testing::assert_sorts_correctly(BinaryHeap::new(), &[]);
+    }
+
+    #[test]
+    fn pop_max_removes_largest() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..300).map(|_| rng.gen_range(-100..100)).collect();
+        let mut expected = values.clone();
+        expected.sort();
+
+        let mut h = BinaryHeap::from_vec(values);
+        while let Some(max) = h.pop_max() {
+            assert_eq!(max, expected.pop().unwrap());
+            assert!(is_valid(&h));
+        }
+        assert!(expected.is_empty());
+    }
+
+    #[test]
+    fn map_in_place_keeps_heap_valid() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..500).map(|_| rng.gen_range(-100..100)).collect();
+        let mut h = BinaryHeap::from_vec(values.clone());
+
+        h.map_in_place(|v| *v -= 7);
+        assert!(is_valid(&h));
+        let mut expected: Vec<i32> = values.iter().map(|v| v - 7).collect();
+        expected.sort();
+        assert_eq!(h.into_sorted_vec(), expected);
+
+        // Changes that reverse the order are handled by the rebuild.
+        let mut h = BinaryHeap::from_vec(values.clone());
+        h.map_in_place(|v| *v = -*v);
+        assert!(is_valid(&h));
+        assert_eq!(h.pop(), values.iter().max().map(|v| -v));
     }
-}
 
-impl<T: Ord> BinaryHeap<T> {
-    /// Returns an empty binary heap.
-    pub fn new() -> Self {
-        BinaryHeap { values: vec![] }
+    #[test]
+    fn update_top_sinks_increased_top() {
+        let mut h = BinaryHeap::from_vec(vec![5, 1, 8, 3, 9, 2]);
+        assert!(h.update_top(|v| v + 10));
+        assert!(is_valid(&h));
+        assert_eq!(h.top(), Some(&2));
+        assert_eq!(h.into_sorted_vec(), vec![2, 3, 5, 8, 9, 11]);
+
+        let mut single = BinaryHeap::from_vec(vec![4]);
+        assert!(single.update_top(|v| v * 2));
+        assert_eq!(single.pop(), Some(8));
+
+        let mut empty = BinaryHeap::<i32>::new();
+        assert!(!empty.update_top(|_| unreachable!()));
     }
-}
 
-impl<T: Ord> MinHeap<T> for BinaryHeap<T> {
-    fn push(&mut self, val: T) {
-        self.values.push(val);
-        let mut current = self.values.len() - 1;
+    #[test]
+    fn update_top_keeps_heap_valid_when_closure_panics() {
+        let mut h = BinaryHeap::from_vec((0..50).collect::<Vec<i32>>());
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            h.update_top(|_| panic!("update failed"));
+        }));
+        assert!(result.is_err());
+        assert_eq!(h.len(), 49);
+        assert!(is_valid(&h));
+        assert_eq!(h.into_sorted_vec(), (1..50).collect::<Vec<i32>>());
+    }
 
-        loop {
-            let f = match father(current) {
-                Some(node) => node,
-                None => break,
-            };
-            if self.values[current] >= self.values[f] {
-                break;
-            }
-            self.values.swap(current, f);
-            current = f;
-        }
+    #[test]
+    fn iter_sorted_yields_ascending_without_changing_heap() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..300).map(|_| rng.gen_range(-50..50)).collect();
+        let h = BinaryHeap::from_vec(values.clone());
+        let before = h.values.clone();
+
+        let mut iter = h.iter_sorted();
+        assert_eq!(iter.len(), values.len());
+        iter.next();
+        assert_eq!(iter.len(), values.len() - 1);
+
+        let mut expected = values;
+        expected.sort();
+        assert_eq!(h.iter_sorted().collect::<Vec<i32>>(), expected);
+        assert_eq!(h.values, before);
     }
 
-    fn top(&self) -> Option<&T> {
-        self.values.get(0)
+    #[test]
+    fn pop_n_while_stops_at_count() {
+        let mut h = BinaryHeap::from_vec(vec![7, 3, 9, 1, 5]);
+        assert_eq!(h.pop_n_while(3, |_| true), vec![1, 3, 5]);
+        assert_eq!(h.len(), 2);
+        assert_eq!(h.pop_n_while(0, |_| true), vec![]);
+        assert_eq!(h.pop_n_while(10, |_| true), vec![7, 9]);
+        assert!(h.is_empty());
     }
 
-    fn pop(&mut self) -> Option<T> {
-        if self.values.is_empty() {
-            return None;
-        }
+    #[test]
+    fn pop_n_while_stops_at_predicate() {
+        let mut h = BinaryHeap::from_vec(vec![7, 3, 9, 1, 5]);
+        assert_eq!(h.pop_n_while(4, |v| *v < 4), vec![1, 3]);
+        assert_eq!(h.top(), Some(&5));
+        assert_eq!(h.len(), 3);
+    }
 
-        if self.values.len() == 1 {
-            return self.values.pop();
-        }
+    #[test]
+    fn deferred_edits_share_one_rebuild() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..1000).map(|_| rng.gen_range(-500..500)).collect();
+        let mut h = BinaryHeap::with_deferred_rebuild(true);
+        h.extend(values.clone());
 
-        let mut last = self.values.len() - 1;
-        self.values.swap(0, last);
-        let top = self.values.pop();
-        last -= 1;
+        let rebuilds = REBUILDS.with(Cell::get);
+        h.retain_count(|v| v % 3 != 0);
+        h.map_in_place(|v| *v = -*v);
+        h.retain_count(|v| *v < 400);
+        h.push(1000);
+        h.map_in_place(|v| *v += 1);
+        assert_eq!(REBUILDS.with(Cell::get), rebuilds);
 
-        let mut current_node: usize = 0;
+        let mut expected: Vec<i32> = values.iter().filter(|v| *v % 3 != 0).map(|v| -v).collect();
+        expected.retain(|v| *v < 400);
+        expected.push(1000);
+        let mut expected: Vec<i32> = expected.into_iter().map(|v| v + 1).collect();
+        expected.sort();
 
-        loop {
-            let left_son = left_son(current_node);
-            let right_son = right_son(current_node);
+        // Queries on a dirty heap scan the elements instead of rebuilding.
+        assert_eq!(h.top(), expected.first());
+        assert_eq!(h.max(), expected.last());
+        assert_eq!(REBUILDS.with(Cell::get), rebuilds);
 
-            // Stop if the current node doesn't have any children.
-            if left_son > last {
-                break;
-            }
+        assert_eq!(h.pop(), Some(expected[0]));
+        assert_eq!(REBUILDS.with(Cell::get), rebuilds + 1);
+        assert!(is_valid(&h));
+        assert_eq!(h.into_sorted_vec(), expected[1..]);
+        assert_eq!(REBUILDS.with(Cell::get), rebuilds + 1);
+    }
 
-            // Find the smallest child.
-            let mut smallest = left_son;
-            if right_son <= last && self.values[right_son] < self.values[left_son] {
-                smallest = right_son;
-            }
+    #[test]
+    fn eager_edits_rebuild_every_time() {
+        let mut h = BinaryHeap::from_vec((0..100).collect::<Vec<i32>>());
+        let rebuilds = REBUILDS.with(Cell::get);
+        h.retain_count(|v| v % 2 == 1);
+        h.map_in_place(|v| *v = -*v);
+        assert_eq!(REBUILDS.with(Cell::get), rebuilds + 2);
+        assert!(is_valid(&h));
+        assert_eq!(h.top(), Some(&-99));
+    }
 
-            // Stop if the current node is where it's supposed to be.
-            if self.values[current_node] <= self.values[smallest] {
-                break;
-            }
+    #[test]
+    fn dirty_heap_answers_queries_in_order() {
+        let mut h = BinaryHeap::with_deferred_rebuild(true);
+        h.extend([4, 8, 1, 9, 3]);
+        h.map_in_place(|v| *v = 10 - *v);
+        assert_eq!(h.top_two(), Some((&1, &2)));
+        assert_eq!(h.iter_sorted().collect::<Vec<i32>>(), vec![1, 2, 6, 7, 9]);
+        assert!(h.update_top(|v| v + 100));
+        assert_eq!(h.pop_max(), Some(101));
+        assert_eq!(h.pop_n_while(10, |_| true), vec![2, 6, 7, 9]);
+    }
+
+    #[test]
+    fn capacity_ceiling_is_never_exceeded() {
+        let mut rng = thread_rng();
+        let mut h = BinaryHeap::with_capacity_ceiling(50);
+        let mut values = vec![];
+        for _ in 0..2000 {
+            let v: i32 = rng.gen_range(-1000..1000);
+            values.push(v);
+            h.push(v);
+            assert!(h.len() <= 50);
+            assert!(h.capacity() <= 50);
+        }
+        assert!(is_valid(&h));
+
+        // Only the 50 smallest values survive.
+        values.sort();
+        values.truncate(50);
+        assert_eq!(h.into_sorted_vec(), values);
 
-            self.values.swap(current_node, smallest);
-            current_node = smallest;
+        // A larger bound is clamped to the ceiling.
+        let mut h = BinaryHeap::with_capacity_ceiling(3);
+        for v in 0..10 {
+            h.push_bounded(v, 10);
+            assert!(h.len() <= 3);
         }
+        assert_eq!(h.into_sorted_vec(), vec![0, 1, 2]);
 
-        top
+        // swap_top on an empty heap doesn't insert past a ceiling of 0.
+        let mut h = BinaryHeap::with_capacity_ceiling(0);
+        assert_eq!(h.swap_top(4), Some(4));
+        assert!(h.is_empty());
     }
 
-    fn len(&self) -> usize {
-        self.values.len()
+    #[test]
+    fn capacity_ceiling_is_not_allocated_upfront() {
+        let mut h = BinaryHeap::with_capacity_ceiling(usize::MAX);
+        assert_eq!(h.capacity(), 0);
+        h.extend((0..100).rev());
+        assert!(h.capacity() < 1000);
+        assert_eq!(h.into_sorted_vec(), (0..100).collect::<Vec<i32>>());
+
+        let mut h = BinaryHeap::with_capacity_ceiling(3);
+        h.extend(0..10);
+        assert_eq!(h.capacity(), 3);
     }
 
-    fn is_empty(&self) -> bool {
-        self.values.is_empty()
+    #[test]
+    fn checked_push_respects_the_capacity_ceiling() {
+        let mut h = BinaryHeap::with_capacity_ceiling(2);
+        assert_eq!(h.checked_push(2, 10), Ok(()));
+        assert_eq!(h.checked_push(1, 10), Ok(()));
+        assert_eq!(h.checked_push(100, 10), Err(100));
+        assert_eq!(h.checked_push(0, 10), Err(0));
+        assert_eq!(h.into_sorted_vec(), vec![1, 2]);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rand::distributions::Standard;
-    use rand::prelude::*;
-    use std::cmp::Reverse;
-    use std::collections;
+    #[test]
+    fn reserving_respects_the_capacity_ceiling() {
+        let mut h = BinaryHeap::with_capacity_ceiling(4);
+        h.push(1);
+        h.reserve(1000);
+        assert!(h.capacity() <= 4);
+        assert!(h.try_reserve(usize::MAX).is_ok());
+        assert!(h.capacity() <= 4);
+        h.clear_and_reserve(1000);
+        assert!(h.is_empty());
+        assert!(h.capacity() <= 4);
+    }
 
-    enum Operation<T> {
-        Insert(T),
-        Pop(),
+    #[test]
+    fn push_clamped_evicts_the_largest() {
+        let mut h = BinaryHeap::with_capacity_ceiling(3);
+        assert_eq!(h.push_clamped(4), None);
+        assert_eq!(h.push_clamped(9), None);
+        assert_eq!(h.push_clamped(6), None);
+
+        assert_eq!(h.push_clamped(1), Some(9));
+        assert_eq!(h.push_clamped(7), Some(7));
+        assert_eq!(h.len(), 3);
+        assert_eq!(h.into_sorted_vec(), vec![1, 4, 6]);
+
+        let mut unbounded = BinaryHeap::new();
+        for v in 0..100 {
+            assert_eq!(unbounded.push_clamped(v), None);
+        }
+        assert_eq!(unbounded.len(), 100);
+
+        let mut extended = BinaryHeap::with_capacity_ceiling(10);
+        extended.extend((0..100).rev());
+        assert_eq!(extended.into_sorted_vec(), (0..10).collect::<Vec<i32>>());
     }
 
     #[test]
-    fn empty_heap_is_empty() {
-        let h = BinaryHeap::<usize>::new();
-        assert!(h.is_empty());
+    fn push_bounded_covers_every_branch() {
+        let mut h = BinaryHeap::new();
+        // Under the bound, the value is inserted.
+        assert_eq!(h.push_bounded(5, 3), None);
+        assert_eq!(h.push_bounded(8, 3), None);
+        assert_eq!(h.push_bounded(2, 3), None);
+
+        // At the bound, a smaller value replaces the maximum.
+        assert_eq!(h.push_bounded(3, 3), Some(8));
+        assert!(is_valid(&h));
+
+        // A value that isn't smaller than the maximum is returned unchanged.
+        assert_eq!(h.push_bounded(5, 3), Some(5));
+        assert_eq!(h.push_bounded(7, 3), Some(7));
+        assert_eq!(h.into_sorted_vec(), vec![2, 3, 5]);
     }
 
     #[test]
-    fn nonempty_heap_is_not_empty() {
-        let mut h = BinaryHeap::<usize>::new();
-        h.push(10);
-        assert!(!h.is_empty());
+    fn push_bounded_keeps_smallest_values() {
+        let mut rng = thread_rng();
+        let mut values: Vec<i32> = (0..1000).map(|_| rng.gen()).collect();
+        let mut h = BinaryHeap::new();
+        for &v in &values {
+            h.push_bounded(v, 20);
+        }
+        values.sort();
+        values.truncate(20);
+        assert_eq!(h.into_sorted_vec(), values);
     }
 
     #[test]
-    fn empty_heap_has_zero_len() {
-        let h = BinaryHeap::<usize>::new();
-        assert_eq!(h.len(), 0);
+    fn into_sorted_vec_desc_is_non_increasing() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..500).map(|_| rng.gen_range(-50..50)).collect();
+        let mut expected = values.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+
+        let sorted = BinaryHeap::from_vec(values).into_sorted_vec_desc();
+        assert!(sorted.windows(2).all(|w| w[0] >= w[1]));
+        assert_eq!(sorted, expected);
+        assert!(BinaryHeap::<i32>::new().into_sorted_vec_desc().is_empty());
     }
 
     #[test]
-    fn insert_increases_heap_len() {
-        let mut h = BinaryHeap::<usize>::new();
-        h.push(2);
-        h.push(10);
-        h.push(4);
-        h.push(100);
-        assert_eq!(h.len(), 4);
+    fn count_less_than_matches_brute_force() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..1000).map(|_| rng.gen_range(-100..100)).collect();
+        let h = BinaryHeap::from_vec(values.clone());
+        for threshold in [-101, -100, -3, 0, 42, 99, 100] {
+            let expected = values.iter().filter(|v| **v < threshold).count();
+            assert_eq!(h.count_less_than(&threshold), expected);
+        }
+        assert_eq!(BinaryHeap::new().count_less_than(&0), 0);
     }
 
     #[test]
-    fn pop_decreases_heap_len() {
-        let mut h = BinaryHeap::<usize>::new();
-        h.push(2);
-        h.push(10);
-        h.push(4);
-        h.push(100);
+    fn rank_matches_sorted_position() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..500).map(|_| rng.gen_range(-50..50)).collect();
+        let h = BinaryHeap::from_vec(values.clone());
+        let mut sorted = values;
+        sorted.sort();
 
-        for _ in 0..4 {
-            h.pop();
+        for query in [-60, -50, -1, 0, 7, 49, 60] {
+            assert_eq!(h.rank(&query), sorted.partition_point(|v| *v < query));
         }
-        assert_eq!(h.len(), 0);
+        assert_eq!(h.rank(&sorted[0]), 0);
     }
 
     #[test]
-    fn top_returns_none_on_empty_heap() {
-        let h = BinaryHeap::<usize>::new();
-        assert!(h.top().is_none());
+    fn split_off_largest_partitions_the_elements() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..300).map(|_| rng.gen_range(-50..50)).collect();
+        let mut sorted = values.clone();
+        sorted.sort();
+
+        for k in [1, 7, 150, 299] {
+            let mut h = BinaryHeap::from_vec(values.clone());
+            let largest = h.split_off_largest(k);
+            assert!(is_valid(&h));
+            assert!(is_valid(&largest));
+            assert_eq!(largest.len(), k);
+            assert_eq!(h.into_sorted_vec(), sorted[..300 - k]);
+            assert_eq!(largest.into_sorted_vec(), sorted[300 - k..]);
+        }
     }
 
     #[test]
-    fn top_returns_minimum_element() {
-        let mut h = BinaryHeap::new();
-        let values = vec![1, 10, 3, -4123, 34, 100, 124];
-        for v in values {
-            h.push(v);
+    fn split_off_largest_edge_cases() {
+        let mut h = BinaryHeap::from_vec(vec![3, 1, 2]);
+        assert!(h.split_off_largest(0).is_empty());
+        assert_eq!(h.len(), 3);
+
+        let all = h.split_off_largest(10);
+        assert!(h.is_empty());
+        assert_eq!(all.into_sorted_vec(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn append_from_iter_matches_repeated_push() {
+        let mut rng = thread_rng();
+        let start: Vec<i32> = (0..200).map(|_| rng.gen_range(-100..100)).collect();
+
+        let mut appended = BinaryHeap::from_vec(start.clone());
+        appended.append_from_iter(-50..150);
+        assert!(is_valid(&appended));
+
+        let mut pushed = BinaryHeap::from_vec(start);
+        for v in -50..150 {
+            pushed.push(v);
         }
-        assert_eq!(*h.top().unwrap(), -4123);
+        assert_eq!(appended.len(), 400);
+        assert_eq!(appended.into_sorted_vec(), pushed.into_sorted_vec());
     }
 
     #[test]
-    fn top_changes_on_pop() {
+    fn peek_or_insert_with_inserts_only_when_empty() {
         let mut h = BinaryHeap::new();
-        let values = vec![1, 10, 3, -4123, 34, 100, 124];
+        assert_eq!(*h.peek_or_insert_with(|| 7), 7);
+        assert_eq!(h.len(), 1);
 
-        let mut ordered = values.clone();
-        ordered.sort();
+        h.push(3);
+        assert_eq!(*h.peek_or_insert_with(|| unreachable!()), 3);
+        assert_eq!(h.len(), 2);
+    }
 
-        for v in values.iter() {
-            h.push(*v);
+    #[test]
+    fn from_vec_is_linear() {
+        // heapify sifts down every node once, which costs at most 2 comparisons per level
+        // below it. That sums to less than 2n comparisons, while n pushes can take n log n.
+        let mut previous = 0;
+        for exponent in 10..17 {
+            let n: usize = 1 << exponent;
+            let log_n = exponent;
+            let values: Vec<testing::CountingOrd<usize>> =
+                (0..n).rev().map(testing::CountingOrd).collect();
+
+            testing::reset_comparisons();
+            let h = sift::without_total_order_check(|| BinaryHeap::from_vec(values));
+            let comparisons = testing::comparison_count();
+            assert!(is_valid(&h));
+
+            assert!(comparisons < 2 * n, "{} comparisons for {}", comparisons, n);
+            assert!(
+                comparisons * 4 < n * log_n,
+                "{} comparisons for {}",
+                comparisons,
+                n
+            );
+            // Doubling the size roughly doubles the work.
+            if previous > 0 {
+                assert!(
+                    comparisons < previous * 5 / 2,
+                    "{} after {}",
+                    comparisons,
+                    previous
+                );
+            }
+            previous = comparisons;
         }
+    }
 
-        for i in 0..values.len() - 1 {
-            h.pop();
-            assert_eq!(*h.top().unwrap(), ordered[i + 1]);
+    #[test]
+    fn view_reflects_the_heap() {
+        let mut h = BinaryHeap::from_vec(vec![5, 3, 8, 1]);
+        {
+            let view = h.view();
+            let copy = view;
+            assert_eq!(view.top(), Some(&1));
+            assert_eq!(copy.len(), 4);
+            assert!(!view.is_empty());
+            assert_eq!(view.as_slice(), h.values.as_slice());
+            let mut seen: Vec<i32> = view.iter().copied().collect();
+            seen.sort();
+            assert_eq!(seen, vec![1, 3, 5, 8]);
         }
+
+        h.pop();
+        let view = h.view();
+        assert_eq!(view.top(), Some(&3));
+        assert_eq!(view.len(), 3);
+        assert!(BinaryHeap::<i32>::new().view().is_empty());
     }
 
     #[test]
-    fn pop_returns_none_on_empty_heap() {
-        let mut h = BinaryHeap::<usize>::new();
-        assert!(h.pop().is_none())
+    fn view_of_a_dirty_heap() {
+        let mut h = BinaryHeap::with_deferred_rebuild(true);
+        h.extend([8, 2, 3]);
+        h.map_in_place(|v| *v = 10 - *v);
+
+        let view = h.view();
+        assert_eq!(view.top(), Some(&2));
+        assert_eq!(view.len(), 3);
+        let mut seen = view.as_slice().to_vec();
+        seen.sort();
+        assert_eq!(seen, vec![2, 7, 8]);
+
+        // Once rebuilt, the slice is in heap order again.
+        h.rebuild();
+        assert_eq!(h.view().as_slice()[0], 2);
     }
 
     #[test]
-    fn pop_returns_and_erases_minimum_element() {
+    fn into_parts_round_trips_without_reallocating() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..200).map(|_| rng.gen_range(-50..50)).collect();
+        let h = BinaryHeap::from_vec(values);
+        let layout = h.values.clone();
+
+        let parts = h.into_parts();
+        let ptr = parts.as_ptr();
+        assert_eq!(parts, layout);
+        let h = BinaryHeap::from_vec_validated(parts).unwrap();
+        assert_eq!(h.values.as_ptr(), ptr);
+
+        let h = unsafe { BinaryHeap::from_vec_unchecked(h.into_parts()) };
+        assert_eq!(h.values, layout);
+
+        // A dirty heap is rebuilt before handing out its storage.
+        let mut h = BinaryHeap::with_deferred_rebuild(true);
+        h.extend(0..50);
+        h.map_in_place(|v| *v = -*v);
+        assert!(BinaryHeap::from_vec_validated(h.into_parts()).is_ok());
+    }
+
+    #[test]
+    fn feed_bounded_keeps_k_smallest_of_a_stream() {
+        let mut rng = StdRng::seed_from_u64(200);
+        let k = 16;
         let mut h = BinaryHeap::new();
-        let values = vec![1, 10, 3, -4123, 34, 100, 124];
+        let mut smallest = collections::BTreeSet::new();
 
-        let mut ordered = values.clone();
-        ordered.sort();
+        for _ in 0..1_000_000 {
+            let v: u64 = rng.gen();
+            h.feed_bounded(v, k);
+            assert!(h.len() <= k);
 
-        for v in values.iter() {
-            h.push(*v);
+            smallest.insert(v);
+            if smallest.len() > k {
+                smallest.pop_last();
+            }
         }
+        assert_eq!(
+            h.into_sorted_vec(),
+            smallest.into_iter().collect::<Vec<u64>>()
+        );
+    }
 
-        for i in 0..values.len() {
-            assert_eq!(h.pop().unwrap(), ordered[i]);
+    #[test]
+    fn push_all_tracking_min_records_prefix_minima() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..300).map(|_| rng.gen_range(-1000..1000)).collect();
+        let mut h = BinaryHeap::new();
+        let minima = h.push_all_tracking_min(values.clone());
+
+        assert!(minima.windows(2).all(|w| w[0] >= w[1]));
+        let prefix_minima: Vec<i32> = values
+            .iter()
+            .scan(i32::MAX, |min, &v| {
+                *min = (*min).min(v);
+                Some(*min)
+            })
+            .collect();
+        assert_eq!(minima, prefix_minima);
+        assert_eq!(h.len(), 300);
+
+        // Minima of a nonempty heap also account for the elements already in it.
+        let mut h = BinaryHeap::from_vec(vec![0]);
+        assert_eq!(h.push_all_tracking_min([5, -1, 3]), vec![0, -1, -1]);
+    }
+
+    // Ordered by key only, so elements with equal keys but different ids compare equal.
+    #[derive(Debug)]
+    struct Task {
+        key: i32,
+        id: usize,
+    }
+
+    impl PartialEq for Task {
+        fn eq(&self, other: &Self) -> bool {
+            self.key == other.key
         }
     }
 
-    // The operation gen strategy.
-    fn strategy<T>(rng: &mut ThreadRng, heap_size: usize) -> Operation<T>
-    where
-        Standard: Distribution<T>,
-    {
-        if heap_size > 0 {
-            let should_pop = rng.gen_bool(0.3);
-            if should_pop {
-                return Operation::Pop();
-            }
+    impl Eq for Task {}
+
+    impl PartialOrd for Task {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Task {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.key.cmp(&other.key)
         }
-        Operation::Insert(rng.gen())
     }
 
-    // Random fuzz testing.
     #[test]
-    #[ignore]
-    fn fuzz() {
-        let num_operations: usize = 5000000;
+    fn into_sorted_vec_by_breaks_ties_deterministically() {
         let mut rng = thread_rng();
+        let mut tasks: Vec<(i32, usize)> = (0..200).map(|id| (rng.gen_range(0..5), id)).collect();
 
-        let mut ours = BinaryHeap::<i32>::new();
-        let mut theirs = collections::BinaryHeap::<Reverse<i32>>::new();
+        let mut orders = vec![];
+        for _ in 0..3 {
+            tasks.shuffle(&mut rng);
+            let h: BinaryHeap<Task> = tasks.iter().map(|&(key, id)| Task { key, id }).collect();
+            let sorted = h.into_sorted_vec_by(|a, b| a.id.cmp(&b.id));
+            orders.push(sorted.iter().map(|t| (t.key, t.id)).collect::<Vec<_>>());
+        }
 
-        for _ in 0..num_operations {
-            let op = strategy(&mut rng, ours.len());
-            match op {
-                Operation::Insert(val) => {
-                    ours.push(val);
-                    theirs.push(Reverse(val));
-                }
-                Operation::Pop() => {
-                    assert_eq!(ours.pop().unwrap(), theirs.pop().unwrap().0);
-                }
-            }
+        let mut expected = tasks;
+        expected.sort();
+        for order in orders {
+            assert_eq!(order, expected);
+        }
+    }
+
+    #[test]
+    fn from_sorted_vec_accepts_sorted_input_in_place() {
+        let h = BinaryHeap::from_sorted_vec(vec![-3, 0, 0, 2, 5, 9, 11]).unwrap();
+        assert_eq!(h.values, vec![-3, 0, 0, 2, 5, 9, 11]);
+        assert!(is_valid(&h));
+
+        let values: Vec<i32> = (0..100).collect();
+        let ptr = values.as_ptr();
+        let h = BinaryHeap::from_sorted_vec(values).unwrap();
+        assert_eq!(h.values.as_ptr(), ptr);
+        assert_eq!(h.values, (0..100).collect::<Vec<i32>>());
+        assert!(BinaryHeap::<i32>::from_sorted_vec(vec![]).is_ok());
+    }
+
+    #[test]
+    fn from_sorted_vec_rejects_unsorted_input() {
+        // This is a valid heap, but not sorted.
+        let values = vec![1, 3, 2];
+        assert_eq!(
+            BinaryHeap::from_sorted_vec(values).err(),
+            Some(vec![1, 3, 2])
+        );
+    }
+
+    #[test]
+    fn leaves_are_the_last_half() {
+        let h = BinaryHeap::from_vec(vec![7, 3, 9, 1, 5, 2, 8]);
+        assert_eq!(h.leaves(), &h.values[3..7]);
+        assert!(h.leaves().contains(&9));
+        assert_eq!(h.leaves().iter().max(), h.max());
+
+        assert!(BinaryHeap::<i32>::new().leaves().is_empty());
+        assert_eq!(BinaryHeap::from_vec(vec![4]).leaves(), &[4]);
+    }
+
+    #[test]
+    fn from_iter_capped_accepts_iterators_under_the_limit() {
+        let h = BinaryHeap::from_iter_capped((0..10).rev(), 10).unwrap();
+        assert_eq!(h.into_sorted_vec(), (0..10).collect::<Vec<i32>>());
+
+        let filtered = (0..100).filter(|v| v % 10 == 0);
+        assert_eq!(
+            BinaryHeap::from_iter_capped(filtered, 10).unwrap().len(),
+            10
+        );
+        assert!(BinaryHeap::from_iter_capped(0..0, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn from_iter_capped_rejects_iterators_over_the_limit() {
+        let error = BinaryHeap::from_iter_capped(0..11, 10).err().unwrap();
+        assert_eq!(error.max(), 10);
+        assert_eq!(error.to_string(), "more than 10 elements");
+
+        // An unbounded iterator without a useful size hint is stopped after max + 1 elements.
+        let mut pulled = 0;
+        let endless = std::iter::from_fn(|| {
+            pulled += 1;
+            Some(pulled)
+        });
+        assert!(BinaryHeap::from_iter_capped(endless, 1000).is_err());
+        assert_eq!(pulled, 1001);
+    }
+
+    #[test]
+    fn into_sorted_vec_map_keeps_original_order() {
+        let h = BinaryHeap::from_vec(vec![10, 9, 100, 1, 25]);
+        // Sorted as strings, these would be in a different order.
+        let mapped = h.into_sorted_vec_map(|v| v.to_string());
+        assert_eq!(mapped, vec!["1", "9", "10", "25", "100"]);
+
+        let h = BinaryHeap::from_vec(vec![3u32, 1, 2]);
+        assert_eq!(h.into_sorted_vec_map(|v| -(v as i32)), vec![-1, -2, -3]);
+    }
+
+    #[test]
+    fn drain_sorted_into_appends_after_existing_elements() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..300).map(|_| rng.gen_range(-100..100)).collect();
+        let mut h = BinaryHeap::from_vec(values.clone());
+        let mut out = vec![500, -500, 7];
+
+        h.drain_sorted_into(&mut out);
+        assert!(h.is_empty());
+        assert_eq!(out[..3], [500, -500, 7]);
+
+        let mut expected = values;
+        expected.sort();
+        assert_eq!(out[3..], expected);
+
+        // The drained heap is still usable.
+        h.push(2);
+        h.push(1);
+        assert_eq!(h.pop(), Some(1));
+    }
+
+    #[test]
+    fn display_is_sorted_and_layout_independent() {
+        let a = BinaryHeap::from_vec(vec![3, 1, 2, 5, 4]);
+        let mut b = BinaryHeap::new();
+        for v in [5, 4, 3, 2, 1] {
+            b.push(v);
         }
+        assert_ne!(a.values, b.values);
+        assert_eq!(a.to_string(), "[1, 2, 3, 4, 5]");
+        assert_eq!(b.to_string(), a.to_string());
+        assert_eq!(BinaryHeap::<i32>::new().to_string(), "[]");
+        assert_eq!(BinaryHeap::from_vec(vec!["b", "a"]).to_string(), "[a, b]");
+    }
+
+    #[test]
+    fn debug_shows_heap_order() {
+        let h = unsafe { BinaryHeap::from_vec_unchecked(vec![1, 3, 2]) };
+        assert_eq!(format!("{:?}", h), "[1, 3, 2]");
+    }
+
+    #[test]
+    fn into_iter_sorted_desc_is_non_increasing() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..400).map(|_| rng.gen_range(-100..100)).collect();
+        let mut expected = values.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+
+        let mut iter = BinaryHeap::from_vec(values).into_iter_sorted_desc();
+        assert_eq!(iter.len(), 400);
+        assert_eq!(iter.next(), expected.first().copied());
+        assert_eq!(iter.len(), 399);
+
+        let rest: Vec<i32> = iter.by_ref().collect();
+        assert!(rest.windows(2).all(|w| w[0] >= w[1]));
+        assert_eq!(rest, expected[1..]);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn concat_combines_different_sources() {
+        let sources: Vec<Box<dyn Iterator<Item = i32>>> = vec![
+            Box::new(vec![9, -1, 4].into_iter()),
+            Box::new([7, 7, 0].into_iter()),
+            Box::new(2..6),
+        ];
+        let h = BinaryHeap::concat(sources);
+        assert!(is_valid(&h));
+        assert_eq!(h.len(), 10);
+        assert_eq!(h.into_sorted_vec(), vec![-1, 0, 2, 3, 4, 4, 5, 7, 7, 9]);
+
+        // Any iterable of iterables works, like a lazy iterator of ranges.
+        let h = BinaryHeap::concat((0..4).map(|i| i * 10..i * 10 + 3));
+        assert!(h.capacity() >= 12);
+        assert_eq!(
+            h.into_sorted_vec(),
+            vec![0, 1, 2, 10, 11, 12, 20, 21, 22, 30, 31, 32]
+        );
+        assert!(BinaryHeap::<i32>::concat(Vec::<Vec<i32>>::new()).is_empty());
     }
 }
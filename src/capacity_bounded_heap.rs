@@ -0,0 +1,112 @@
+use crate::binary_heap::BinaryHeap;
+use crate::MinHeap;
+use std::cmp::Ord;
+
+/// A capacity bounded heap is a min-heap that never holds more than a fixed number of elements.
+pub struct CapacityBoundedHeap<T: Ord> {
+    heap: BinaryHeap<T>,
+    max: usize,
+}
+
+impl<T: Ord> CapacityBoundedHeap<T> {
+    /// Returns an empty heap that holds at most max elements.
+    pub fn new(max: usize) -> Self {
+        CapacityBoundedHeap {
+            heap: BinaryHeap::new(),
+            max,
+        }
+    }
+
+    /// Insert a new value in the heap, unless it is full.
+    /// Returns the value back if it was not inserted.
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        self.heap.checked_push(value, self.max)
+    }
+
+    /// Insert a new value in the heap. If the heap is full, the largest element is
+    /// evicted to make room and returned, as long as the new value is smaller than it.
+    /// Otherwise, the new value is returned back without being inserted.
+    /// Evicting scans the leaves of the heap in O(n/2).
+    pub fn push_evicting(&mut self, value: T) -> Option<T> {
+        self.heap.push_bounded(value, self.max)
+    }
+
+    /// Returns an imutable borrow to the smallest element in the heap.
+    /// Return None if the heap is empty.
+    pub fn top(&self) -> Option<&T> {
+        self.heap.top()
+    }
+
+    /// Returns ownership of the smallest element in the heap and deletes it from the heap.
+    /// Return None if the heap is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        self.heap.pop()
+    }
+
+    /// Returns the number of elements in the heap.
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Returns true if the heap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns true if the heap holds the maximum number of elements.
+    pub fn is_full(&self) -> bool {
+        self.heap.len() >= self.max
+    }
+
+    /// Returns the maximum number of elements the heap can hold.
+    pub fn max_len(&self) -> usize {
+        self.max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_push_rejects_when_full() {
+        let mut h = CapacityBoundedHeap::new(3);
+        assert_eq!(h.try_push(5), Ok(()));
+        assert_eq!(h.try_push(1), Ok(()));
+        assert_eq!(h.try_push(9), Ok(()));
+        assert!(h.is_full());
+
+        assert_eq!(h.try_push(0), Err(0));
+        assert_eq!(h.len(), 3);
+        assert_eq!(h.top(), Some(&1));
+    }
+
+    #[test]
+    fn push_evicting_evicts_the_maximum() {
+        let mut h = CapacityBoundedHeap::new(3);
+        for v in [5, 1, 9] {
+            assert_eq!(h.push_evicting(v), None);
+        }
+
+        assert_eq!(h.push_evicting(3), Some(9));
+        assert_eq!(h.push_evicting(0), Some(5));
+        assert_eq!(h.len(), 3);
+
+        let popped: Vec<i32> = std::iter::from_fn(|| h.pop()).collect();
+        assert_eq!(popped, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn push_evicting_returns_values_that_are_too_large() {
+        let mut h = CapacityBoundedHeap::new(2);
+        h.push_evicting(4);
+        h.push_evicting(2);
+        assert_eq!(h.push_evicting(4), Some(4));
+        assert_eq!(h.push_evicting(10), Some(10));
+        assert_eq!(h.len(), 2);
+
+        let mut empty = CapacityBoundedHeap::new(0);
+        assert_eq!(empty.push_evicting(1), Some(1));
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+use crate::binary_heap::BinaryHeap;
+use crate::MinHeap;
+use std::cmp::Ordering;
+
+// A scheduled event. Entries are ordered by time, then by insertion order.
+struct Entry<E> {
+    time: u64,
+    seq: u64,
+    event: E,
+}
+
+impl<E> PartialEq for Entry<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<E> Eq for Entry<E> {}
+
+impl<E> PartialOrd for Entry<E> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<E> Ord for Entry<E> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.time, self.seq).cmp(&(other.time, other.seq))
+    }
+}
+
+/// An event queue for discrete-event simulation.
+/// Events scheduled for the same time fire in the order they were scheduled.
+pub struct EventQueue<E> {
+    entries: BinaryHeap<Entry<E>>,
+    next_seq: u64,
+}
+
+impl<E> EventQueue<E> {
+    /// Returns an empty event queue.
+    pub fn new() -> Self {
+        EventQueue {
+            entries: BinaryHeap::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Schedule an event to fire at the given time.
+    pub fn schedule(&mut self, time: u64, event: E) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.entries.push(Entry { time, seq, event });
+    }
+
+    /// Returns the next due event along with its time and removes it from the queue.
+    /// Return None if no events are scheduled.
+    pub fn advance(&mut self) -> Option<(u64, E)> {
+        self.entries.pop().map(|entry| (entry.time, entry.event))
+    }
+
+    /// Returns the time of the next due event.
+    /// Return None if no events are scheduled.
+    pub fn next_time(&self) -> Option<u64> {
+        self.entries.top().map(|entry| entry.time)
+    }
+
+    /// Returns the number of scheduled events.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no events are scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<E> Default for EventQueue<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance_on_empty_queue_returns_none() {
+        let mut q = EventQueue::<&str>::new();
+        assert!(q.is_empty());
+        assert!(q.next_time().is_none());
+        assert!(q.advance().is_none());
+    }
+
+    #[test]
+    fn events_fire_in_time_order() {
+        let mut q = EventQueue::new();
+        q.schedule(30, "c");
+        q.schedule(10, "a");
+        q.schedule(50, "e");
+        q.schedule(20, "b");
+        q.schedule(40, "d");
+        assert_eq!(q.len(), 5);
+        assert_eq!(q.next_time(), Some(10));
+
+        let fired: Vec<(u64, &str)> = std::iter::from_fn(|| q.advance()).collect();
+        assert_eq!(
+            fired,
+            vec![(10, "a"), (20, "b"), (30, "c"), (40, "d"), (50, "e")]
+        );
+    }
+
+    #[test]
+    fn simultaneous_events_fire_in_fifo_order() {
+        let mut q = EventQueue::new();
+        q.schedule(5, 1);
+        q.schedule(3, 2);
+        q.schedule(5, 3);
+        q.schedule(3, 4);
+        q.schedule(5, 5);
+
+        let fired: Vec<(u64, i32)> = std::iter::from_fn(|| q.advance()).collect();
+        assert_eq!(fired, vec![(3, 2), (3, 4), (5, 1), (5, 3), (5, 5)]);
+    }
+}
@@ -0,0 +1,351 @@
+use crate::binary_heap::BinaryHeap;
+use crate::MinHeap;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Returns the length of the shortest path from the source to every node.
+/// The graph is given as adjacency lists, where the list at index u holds the (v, weight) edges out of u.
+/// Unreachable nodes get None, and so do nodes whose paths are all longer than u64::MAX.
+pub fn dijkstra(graph: &[Vec<(usize, u64)>], source: usize) -> Vec<Option<u64>> {
+    let mut dist: Vec<Option<u64>> = vec![None; graph.len()];
+    let mut heap = BinaryHeap::new();
+
+    dist[source] = Some(0);
+    heap.push((0, source));
+
+    while let Some((d, u)) = heap.pop() {
+        // Skip entries that were superseded by a shorter path.
+        if dist[u].is_some_and(|best| d > best) {
+            continue;
+        }
+
+        for &(v, weight) in &graph[u] {
+            // A path whose length overflows can't be the shortest one.
+            let Some(candidate) = d.checked_add(weight) else {
+                continue;
+            };
+            if dist[v].is_none_or(|best| candidate < best) {
+                dist[v] = Some(candidate);
+                heap.push((candidate, v));
+            }
+        }
+    }
+
+    dist
+}
+
+/// Returns the edges (u, v, weight) of a minimum spanning tree of an undirected graph.
+/// The graph is given as adjacency lists, with every edge listed from both of its ends.
+/// If the graph is disconnected, only the component containing node 0 is spanned.
+pub fn prim_mst(graph: &[Vec<(usize, u64)>]) -> Vec<(usize, usize, u64)> {
+    let mut edges = vec![];
+    if graph.is_empty() {
+        return edges;
+    }
+
+    let mut in_tree = vec![false; graph.len()];
+    let mut heap = BinaryHeap::new();
+
+    in_tree[0] = true;
+    for &(v, weight) in &graph[0] {
+        heap.push((weight, v, 0));
+    }
+
+    while let Some((weight, v, u)) = heap.pop() {
+        // Skip edges whose endpoint was already reached through a lighter edge.
+        if in_tree[v] {
+            continue;
+        }
+        in_tree[v] = true;
+        edges.push((u, v, weight));
+
+        for &(next, next_weight) in &graph[v] {
+            if !in_tree[next] {
+                heap.push((next_weight, next, v));
+            }
+        }
+    }
+
+    edges
+}
+
+/// Returns the shortest path from start to goal, or None if the goal is unreachable.
+/// The neighbors function lists the (node, weight) edges out of a node, and the heuristic
+/// estimates the remaining distance to the goal. The heuristic must never overestimate
+/// for the returned path to be the shortest. Paths longer than u64::MAX are skipped.
+pub fn a_star<N, H>(
+    start: N,
+    goal: N,
+    neighbors: impl Fn(&N) -> Vec<(N, u64)>,
+    heuristic: H,
+) -> Option<Vec<N>>
+where
+    N: Clone + Eq + Hash,
+    H: Fn(&N) -> u64,
+{
+    // Nodes are numbered in discovery order so the heap doesn't need N: Ord.
+    let mut ids = HashMap::new();
+    let mut nodes = vec![];
+    let mut best: Vec<u64> = vec![];
+    let mut came_from: Vec<Option<usize>> = vec![];
+    let mut heap = BinaryHeap::new();
+
+    ids.insert(start.clone(), 0);
+    heap.push((heuristic(&start), 0, 0));
+    nodes.push(start);
+    best.push(0);
+    came_from.push(None);
+
+    while let Some((_, g, u)) = heap.pop() {
+        // Skip entries that were superseded by a shorter path.
+        if g > best[u] {
+            continue;
+        }
+
+        if nodes[u] == goal {
+            let mut path = vec![nodes[u].clone()];
+            let mut current = u;
+            while let Some(previous) = came_from[current] {
+                path.push(nodes[previous].clone());
+                current = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for (node, weight) in neighbors(&nodes[u]) {
+            // A path whose cost overflows can't be the shortest one.
+            let Some(candidate) = g.checked_add(weight) else {
+                continue;
+            };
+            let v = match ids.get(&node) {
+                Some(&v) => {
+                    if candidate >= best[v] {
+                        continue;
+                    }
+                    best[v] = candidate;
+                    came_from[v] = Some(u);
+                    v
+                }
+                None => {
+                    let v = nodes.len();
+                    ids.insert(node.clone(), v);
+                    nodes.push(node);
+                    best.push(candidate);
+                    came_from.push(Some(u));
+                    v
+                }
+            };
+            // The estimate only orders the heap, so it saturates instead of overflowing.
+            heap.push((candidate.saturating_add(heuristic(&nodes[v])), candidate, v));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dijkstra_single_node() {
+        let graph = vec![vec![]];
+        assert_eq!(dijkstra(&graph, 0), vec![Some(0)]);
+    }
+
+    #[test]
+    fn dijkstra_prefers_cheaper_longer_path() {
+        let graph = vec![
+            vec![(1, 4), (2, 1)],
+            vec![(3, 1)],
+            vec![(1, 2), (3, 5)],
+            vec![],
+        ];
+        assert_eq!(
+            dijkstra(&graph, 0),
+            vec![Some(0), Some(3), Some(1), Some(4)]
+        );
+    }
+
+    #[test]
+    fn dijkstra_skips_overflowing_paths() {
+        let graph = vec![
+            vec![(1, u64::MAX - 1), (2, u64::MAX)],
+            vec![(2, 5), (3, 2)],
+            vec![],
+            vec![],
+        ];
+        assert_eq!(
+            dijkstra(&graph, 0),
+            vec![Some(0), Some(u64::MAX - 1), Some(u64::MAX), None]
+        );
+    }
+
+    #[test]
+    fn dijkstra_disconnected_node_is_unreachable() {
+        let graph = vec![
+            vec![(1, 7), (2, 9), (5, 14)],
+            vec![(0, 7), (2, 10), (3, 15)],
+            vec![(0, 9), (1, 10), (3, 11), (5, 2)],
+            vec![(1, 15), (2, 11), (4, 6)],
+            vec![(3, 6), (5, 9)],
+            vec![(0, 14), (2, 2), (4, 9)],
+            vec![],
+        ];
+        assert_eq!(
+            dijkstra(&graph, 0),
+            vec![
+                Some(0),
+                Some(7),
+                Some(9),
+                Some(20),
+                Some(20),
+                Some(11),
+                None
+            ]
+        );
+    }
+
+    fn undirected(n: usize, edges: &[(usize, usize, u64)]) -> Vec<Vec<(usize, u64)>> {
+        let mut graph = vec![vec![]; n];
+        for &(u, v, weight) in edges {
+            graph[u].push((v, weight));
+            graph[v].push((u, weight));
+        }
+        graph
+    }
+
+    #[test]
+    fn prim_mst_of_empty_graph_is_empty() {
+        assert!(prim_mst(&[]).is_empty());
+    }
+
+    #[test]
+    fn prim_mst_finds_unique_tree() {
+        let graph = undirected(
+            5,
+            &[
+                (0, 1, 2),
+                (0, 3, 6),
+                (1, 2, 3),
+                (1, 3, 8),
+                (1, 4, 5),
+                (2, 4, 7),
+                (3, 4, 9),
+            ],
+        );
+
+        let mut tree = prim_mst(&graph);
+        tree.sort_by_key(|&(_, _, weight)| weight);
+        assert_eq!(tree, vec![(0, 1, 2), (1, 2, 3), (1, 4, 5), (0, 3, 6)]);
+    }
+
+    #[test]
+    fn prim_mst_spans_only_the_component_of_node_zero() {
+        let graph = undirected(5, &[(0, 1, 4), (1, 2, 1), (0, 2, 2), (3, 4, 1)]);
+
+        let tree = prim_mst(&graph);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.iter().map(|&(_, _, weight)| weight).sum::<u64>(), 3);
+        assert!(tree.iter().all(|&(u, v, _)| u < 3 && v < 3));
+    }
+
+    // A grid where '#' cells are obstacles.
+    fn grid_neighbors(grid: &[&str], &(row, col): &(usize, usize)) -> Vec<((usize, usize), u64)> {
+        let mut result = vec![];
+        let moves = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+        for (dr, dc) in moves {
+            let r = row as i64 + dr;
+            let c = col as i64 + dc;
+            if r < 0 || c < 0 || r as usize >= grid.len() || c as usize >= grid[0].len() {
+                continue;
+            }
+            let (r, c) = (r as usize, c as usize);
+            if grid[r].as_bytes()[c] != b'#' {
+                result.push(((r, c), 1));
+            }
+        }
+        result
+    }
+
+    fn manhattan(a: &(usize, usize), b: &(usize, usize)) -> u64 {
+        (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as u64
+    }
+
+    #[test]
+    fn a_star_start_is_goal() {
+        let grid = ["."];
+        let path = a_star((0, 0), (0, 0), |n| grid_neighbors(&grid, n), |_| 0);
+        assert_eq!(path, Some(vec![(0, 0)]));
+    }
+
+    #[test]
+    fn a_star_walks_around_obstacles() {
+        let grid = [
+            "....#...", //
+            ".##.#.#.", //
+            ".#..#.#.", //
+            ".#.##.#.", //
+            "......#.", //
+        ];
+        let goal = (0, 7);
+        let path = a_star(
+            (0, 0),
+            goal,
+            |n| grid_neighbors(&grid, n),
+            |n| manhattan(n, &goal),
+        )
+        .unwrap();
+
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&goal));
+        assert_eq!(path.len() - 1, 15);
+        for step in path.windows(2) {
+            assert_eq!(manhattan(&step[0], &step[1]), 1);
+            assert_ne!(grid[step[1].0].as_bytes()[step[1].1], b'#');
+        }
+    }
+
+    #[test]
+    fn a_star_handles_costs_near_the_maximum() {
+        let edges = |n: &usize| -> Vec<(usize, u64)> {
+            match n {
+                0 => vec![(1, u64::MAX - 10), (2, 1)],
+                1 => vec![(3, 20)],
+                2 => vec![(3, 5)],
+                _ => vec![],
+            }
+        };
+        // The estimate saturates for node 1, which is never expanded.
+        let heuristic = |n: &usize| if *n == 3 { 0 } else { u64::MAX / 2 };
+        assert_eq!(a_star(0, 3, edges, heuristic), Some(vec![0, 2, 3]));
+
+        // A goal that is only reachable through an overflowing path is unreachable.
+        let edges = |n: &usize| -> Vec<(usize, u64)> {
+            match n {
+                0 => vec![(1, u64::MAX)],
+                1 => vec![(2, 1)],
+                _ => vec![],
+            }
+        };
+        assert_eq!(a_star(0, 2, edges, |_| 0), None);
+    }
+
+    #[test]
+    fn a_star_unreachable_goal_is_none() {
+        let grid = [
+            "..#..", //
+            "..#..", //
+            "..#..", //
+        ];
+        let goal = (1, 4);
+        let path = a_star(
+            (1, 0),
+            goal,
+            |n| grid_neighbors(&grid, n),
+            |n| manhattan(n, &goal),
+        );
+        assert!(path.is_none());
+    }
+}
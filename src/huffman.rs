@@ -0,0 +1,130 @@
+use crate::binary_heap::BinaryHeap;
+use crate::MinHeap;
+use std::collections::HashMap;
+
+// A node of the Huffman tree. Children are indices into the node arena.
+enum Node {
+    Leaf(char),
+    Internal(usize, usize),
+}
+
+/// Returns a prefix-free Huffman code for each of the given (symbol, frequency) pairs.
+/// A single symbol is assigned the code "0".
+pub fn huffman_codes(freqs: &[(char, u64)]) -> HashMap<char, String> {
+    let mut codes = HashMap::new();
+    if freqs.is_empty() {
+        return codes;
+    }
+
+    // Frequencies are summed as u128, so merging trees can't overflow.
+    let mut nodes = vec![];
+    let mut heap = BinaryHeap::new();
+    for &(symbol, freq) in freqs {
+        heap.push((u128::from(freq), nodes.len()));
+        nodes.push(Node::Leaf(symbol));
+    }
+
+    // Repeatedly merge the two least frequent trees.
+    while heap.len() > 1 {
+        let (left_freq, left) = heap.pop().unwrap();
+        let (right_freq, right) = heap.pop().unwrap();
+        heap.push((left_freq + right_freq, nodes.len()));
+        nodes.push(Node::Internal(left, right));
+    }
+
+    let (_, root) = heap.pop().unwrap();
+    if let Node::Leaf(symbol) = nodes[root] {
+        codes.insert(symbol, String::from("0"));
+        return codes;
+    }
+
+    let mut stack = vec![(root, String::new())];
+    while let Some((node, code)) = stack.pop() {
+        match nodes[node] {
+            Node::Leaf(symbol) => {
+                codes.insert(symbol, code);
+            }
+            Node::Internal(left, right) => {
+                stack.push((left, code.clone() + "0"));
+                stack.push((right, code + "1"));
+            }
+        }
+    }
+
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_prefix_free(codes: &HashMap<char, String>) -> bool {
+        codes.iter().all(|(a, code_a)| {
+            codes
+                .iter()
+                .all(|(b, code_b)| a == b || !code_b.starts_with(code_a.as_str()))
+        })
+    }
+
+    #[test]
+    fn no_symbols_has_no_codes() {
+        assert!(huffman_codes(&[]).is_empty());
+    }
+
+    #[test]
+    fn single_symbol_gets_code_zero() {
+        let codes = huffman_codes(&[('a', 42)]);
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[&'a'], "0");
+    }
+
+    #[test]
+    fn codes_are_prefix_free() {
+        let freqs = [
+            ('a', 45),
+            ('b', 13),
+            ('c', 12),
+            ('d', 16),
+            ('e', 9),
+            ('f', 5),
+        ];
+        let codes = huffman_codes(&freqs);
+        assert_eq!(codes.len(), freqs.len());
+        assert!(is_prefix_free(&codes));
+        assert_eq!(codes[&'a'].len(), 1);
+    }
+
+    #[test]
+    fn frequencies_near_the_maximum_do_not_overflow() {
+        let freqs = [('a', u64::MAX), ('b', u64::MAX), ('c', 1)];
+        let codes = huffman_codes(&freqs);
+        assert!(is_prefix_free(&codes));
+        assert_eq!(codes[&'b'].len(), 1);
+        assert_eq!(codes[&'a'].len(), 2);
+        assert_eq!(codes[&'c'].len(), 2);
+    }
+
+    #[test]
+    fn frequent_symbols_get_shorter_codes() {
+        let freqs = [
+            ('a', 1),
+            ('b', 1),
+            ('c', 2),
+            ('d', 3),
+            ('e', 5),
+            ('f', 8),
+            ('g', 13),
+            ('h', 21),
+        ];
+        let codes = huffman_codes(&freqs);
+        assert!(is_prefix_free(&codes));
+
+        for &(a, freq_a) in &freqs {
+            for &(b, freq_b) in &freqs {
+                if freq_a > freq_b {
+                    assert!(codes[&a].len() <= codes[&b].len());
+                }
+            }
+        }
+    }
+}
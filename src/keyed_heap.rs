@@ -0,0 +1,105 @@
+use crate::sift;
+use std::cmp::Ord;
+
+/// A keyed heap is a min-heap that orders its elements by a key computed from each element.
+/// The key of an element is computed once, when it is inserted, and cached next to it,
+/// so sifting only compares the cached keys. This pays off when the key is expensive to compute.
+pub struct KeyedHeap<T, K: Ord> {
+    entries: Vec<(K, T)>,
+    key: Box<dyn FnMut(&T) -> K>,
+}
+
+impl<T, K: Ord> KeyedHeap<T, K> {
+    /// Returns an empty keyed heap that orders its elements by the given key function.
+    pub fn new<F: FnMut(&T) -> K + 'static>(key: F) -> Self {
+        KeyedHeap {
+            entries: vec![],
+            key: Box::new(key),
+        }
+    }
+
+    /// Insert a new value in the heap, computing its key once.
+    pub fn push(&mut self, value: T) {
+        let key = (self.key)(&value);
+        self.entries.push((key, value));
+        let last = self.entries.len() - 1;
+        sift::sift_up(&mut self.entries, last, &mut |a, b| a.0.cmp(&b.0));
+    }
+
+    /// Returns an imutable borrow to the element with the smallest key.
+    /// Return None if the heap is empty.
+    pub fn top(&self) -> Option<&T> {
+        self.entries.first().map(|(_, value)| value)
+    }
+
+    /// Returns the smallest key in the heap.
+    /// Return None if the heap is empty.
+    pub fn top_key(&self) -> Option<&K> {
+        self.entries.first().map(|(key, _)| key)
+    }
+
+    /// Returns ownership of the element with the smallest key and deletes it from the heap.
+    /// Return None if the heap is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        let mut top = self.entries.pop()?;
+        if !self.entries.is_empty() {
+            top = std::mem::replace(&mut self.entries[0], top);
+            sift::sift_down(&mut self.entries, 0, &mut |a, b| a.0.cmp(&b.0));
+        }
+        Some(top.1)
+    }
+
+    /// Returns the number of elements in the heap.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the heap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn pops_in_key_order() {
+        let mut h = KeyedHeap::new(|s: &String| s.len());
+        for s in ["ccc", "a", "dddd", "bb"] {
+            h.push(s.to_string());
+        }
+        assert_eq!(h.len(), 4);
+        assert_eq!(h.top_key(), Some(&1));
+
+        let popped: Vec<String> = std::iter::from_fn(|| h.pop()).collect();
+        assert_eq!(popped, vec!["a", "bb", "ccc", "dddd"]);
+        assert!(h.is_empty());
+        assert!(h.top().is_none());
+    }
+
+    #[test]
+    fn key_is_computed_once_per_element() {
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let mut h = KeyedHeap::new(move |v: &i32| {
+            counter.set(counter.get() + 1);
+            -v
+        });
+
+        let mut rng = thread_rng();
+        let mut values: Vec<i32> = (0..500).map(|_| rng.gen_range(-100..100)).collect();
+        for &v in &values {
+            h.push(v);
+        }
+        values.sort_by(|a, b| b.cmp(a));
+        for v in values {
+            assert_eq!(h.pop(), Some(v));
+        }
+        assert_eq!(calls.get(), 500);
+    }
+}
@@ -1,4 +1,24 @@
+pub mod adaptive_heap;
+pub mod aging_queue;
+pub mod arena_binary_heap;
 pub mod binary_heap;
+pub mod capacity_bounded_heap;
+pub mod event_queue;
+pub mod graph;
+pub mod huffman;
+pub mod keyed_heap;
+pub mod median_heap;
+pub mod merge;
 pub mod min_heap;
+pub mod select;
+pub mod sift;
+pub mod sort;
+pub mod std_compat;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod top_k;
+pub mod total_float;
+pub mod tracked_binary_heap;
+pub mod window_min;
 
 pub use min_heap::MinHeap;
@@ -0,0 +1,133 @@
+use crate::binary_heap::BinaryHeap;
+use crate::MinHeap;
+use std::cmp::{Ord, Reverse};
+
+/// The median of the values in a median heap.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MedianResult<T> {
+    /// The middle value, if the number of values is odd.
+    Single(T),
+    /// The two middle values, smallest first, if the number of values is even.
+    Pair(T, T),
+}
+
+/// A median heap keeps track of the running median of a stream of values.
+pub struct MedianHeap<T: Ord + Clone> {
+    // Max-heap holding the lower half. It always has the same number of
+    // elements as the upper half, or one more.
+    lower: BinaryHeap<Reverse<T>>,
+    // Min-heap holding the upper half.
+    upper: BinaryHeap<T>,
+}
+
+impl<T: Ord + Clone> MedianHeap<T> {
+    /// Returns an empty median heap.
+    pub fn new() -> Self {
+        MedianHeap {
+            lower: BinaryHeap::new(),
+            upper: BinaryHeap::new(),
+        }
+    }
+
+    /// Insert a new value in the median heap.
+    pub fn push(&mut self, value: T) {
+        match self.lower.top() {
+            Some(Reverse(max)) if value > *max => self.upper.push(value),
+            _ => self.lower.push(Reverse(value)),
+        }
+
+        // Rebalance so the sizes differ by at most one.
+        if self.lower.len() > self.upper.len() + 1 {
+            let Reverse(max) = self.lower.pop().unwrap();
+            self.upper.push(max);
+        } else if self.upper.len() > self.lower.len() {
+            let min = self.upper.pop().unwrap();
+            self.lower.push(Reverse(min));
+        }
+    }
+
+    /// Returns the median of the values in the median heap.
+    /// Return None if the median heap is empty.
+    pub fn median(&self) -> Option<MedianResult<T>> {
+        let Reverse(max) = self.lower.top()?;
+        if self.lower.len() > self.upper.len() {
+            Some(MedianResult::Single(max.clone()))
+        } else {
+            let min = self.upper.top().unwrap();
+            Some(MedianResult::Pair(max.clone(), min.clone()))
+        }
+    }
+
+    /// Returns the number of values in the median heap.
+    pub fn len(&self) -> usize {
+        self.lower.len() + self.upper.len()
+    }
+
+    /// Returns true if the median heap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lower.is_empty()
+    }
+}
+
+impl<T: Ord + Clone> Default for MedianHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn empty_median_heap_has_no_median() {
+        let h = MedianHeap::<i32>::new();
+        assert!(h.is_empty());
+        assert!(h.median().is_none());
+    }
+
+    #[test]
+    fn running_median_of_known_sequence() {
+        let mut h = MedianHeap::new();
+        let values = vec![5, 15, 1, 3, 8, 7, 9, 10];
+        let expected = vec![
+            MedianResult::Single(5),
+            MedianResult::Pair(5, 15),
+            MedianResult::Single(5),
+            MedianResult::Pair(3, 5),
+            MedianResult::Single(5),
+            MedianResult::Pair(5, 7),
+            MedianResult::Single(7),
+            MedianResult::Pair(7, 8),
+        ];
+
+        for (value, median) in values.into_iter().zip(expected) {
+            h.push(value);
+            assert_eq!(h.median().unwrap(), median);
+        }
+        assert_eq!(h.len(), 8);
+    }
+
+    #[test]
+    fn running_median_matches_sorted_copy() {
+        let mut rng = thread_rng();
+        let mut h = MedianHeap::new();
+        let mut seen = vec![];
+
+        for _ in 0..500 {
+            let value: i32 = rng.gen_range(-100..100);
+            h.push(value);
+            seen.push(value);
+            seen.sort();
+
+            let middle = seen.len() / 2;
+            let expected = if seen.len() % 2 == 1 {
+                MedianResult::Single(seen[middle])
+            } else {
+                MedianResult::Pair(seen[middle - 1], seen[middle])
+            };
+            assert_eq!(h.median().unwrap(), expected);
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::binary_heap::BinaryHeap;
+use crate::MinHeap;
+use std::cmp::Ord;
+use std::iter::FusedIterator;
+use std::num::NonZeroUsize;
+use std::panic;
+use std::thread;
+
+/// An iterator that merges several sorted iterators into one sorted stream.
+pub struct MergeSorted<I, T>
+where
+    I: Iterator<Item = T>,
+    T: Ord,
+{
+    iters: Vec<I>,
+    // The current head of each source, tagged by the index of the source.
+    heads: BinaryHeap<(T, usize)>,
+}
+
+/// Merges the given sorted iterators into one globally sorted iterator.
+pub fn merge_sorted<I, T>(mut iters: Vec<I>) -> MergeSorted<I, T>
+where
+    I: Iterator<Item = T>,
+    T: Ord,
+{
+    let mut heads = BinaryHeap::new();
+    for (source, iter) in iters.iter_mut().enumerate() {
+        if let Some(value) = iter.next() {
+            heads.push((value, source));
+        }
+    }
+    MergeSorted { iters, heads }
+}
+
+impl<I, T> Iterator for MergeSorted<I, T>
+where
+    I: Iterator<Item = T>,
+    T: Ord,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let (value, source) = self.heads.pop()?;
+
+        // Refill from the source the minimum came from.
+        if let Some(next) = self.iters[source].next() {
+            self.heads.push((next, source));
+        }
+        Some(value)
+    }
+}
+
+// Once every head is consumed, the heap stays empty, whatever the sources do.
+impl<I, T> FusedIterator for MergeSorted<I, T>
+where
+    I: Iterator<Item = T>,
+    T: Ord,
+{
+}
+
+/// Merges the given sorted vectors into a single sorted vector.
+pub fn merge_k_sorted_vecs<T: Ord>(vecs: Vec<Vec<T>>) -> Vec<T> {
+    let total = vecs.iter().map(Vec::len).sum();
+    let mut merged = Vec::with_capacity(total);
+
+    let iters = vecs
+        .into_iter()
+        .filter(|v| !v.is_empty())
+        .map(Vec::into_iter)
+        .collect();
+    merged.extend(merge_sorted(iters));
+    merged
+}
+
+/// Consumes the given heaps and returns an iterator over the union of their elements, in ascending order.
+/// A meta-heap keeps the top of every heap, so each element is found in O(log k).
+pub fn merge_heaps_sorted<T: Ord>(heaps: Vec<BinaryHeap<T>>) -> impl Iterator<Item = T> {
+    let sources = heaps
+        .into_iter()
+        .map(|mut heap| std::iter::from_fn(move || heap.pop()))
+        .collect();
+    merge_sorted(sources)
+}
+
+/// Merges the given sorted slices into a single sorted vector, using all available cores.
+/// Runs are merged pairwise in a tournament, with the two halves of every round
+/// merged on separate threads.
+pub fn par_merge_sorted<T: Ord + Clone + Send + Sync>(slices: Vec<&[T]>) -> Vec<T> {
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    merge_runs(&slices, threads)
+}
+
+// Merges the runs, splitting the work between the given number of threads.
+fn merge_runs<T: Ord + Clone + Send + Sync>(slices: &[&[T]], threads: usize) -> Vec<T> {
+    match slices {
+        [] => vec![],
+        [only] => only.to_vec(),
+        [a, b] => merge_two(a.iter().cloned(), b.iter().cloned()),
+        _ => {
+            let (left, right) = slices.split_at(slices.len() / 2);
+            let (left, right) = if threads > 1 {
+                thread::scope(|s| {
+                    let handle = s.spawn(|| merge_runs(left, threads / 2));
+                    let right = merge_runs(right, threads - threads / 2);
+                    let left = handle.join().unwrap_or_else(|e| panic::resume_unwind(e));
+                    (left, right)
+                })
+            } else {
+                (merge_runs(left, 1), merge_runs(right, 1))
+            };
+            merge_two(left.into_iter(), right.into_iter())
+        }
+    }
+}
+
+// Merges two sorted runs with the heap-based merge.
+fn merge_two<I, T>(a: I, b: I) -> Vec<T>
+where
+    I: ExactSizeIterator<Item = T>,
+    T: Ord,
+{
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    merged.extend(merge_sorted(vec![a, b]));
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn merge_no_iterators_is_empty() {
+        let iters: Vec<std::vec::IntoIter<i32>> = vec![];
+        assert_eq!(merge_sorted(iters).count(), 0);
+    }
+
+    #[test]
+    fn merge_three_sorted_vectors() {
+        let a = vec![1, 4, 7, 10];
+        let b = vec![2, 5, 8];
+        let c = vec![-3, 3, 6, 9, 12];
+
+        let merged: Vec<i32> =
+            merge_sorted(vec![a.into_iter(), b.into_iter(), c.into_iter()]).collect();
+        assert_eq!(merged, vec![-3, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12]);
+    }
+
+    #[test]
+    fn merge_keeps_duplicates_and_empty_sources() {
+        let a = vec![1, 1, 2];
+        let b = vec![];
+        let c = vec![1, 2, 2];
+
+        let merged: Vec<i32> =
+            merge_sorted(vec![a.into_iter(), b.into_iter(), c.into_iter()]).collect();
+        assert_eq!(merged, vec![1, 1, 1, 2, 2, 2]);
+    }
+
+    // A source that yields again after returning None.
+    struct Flaky(u32);
+
+    impl Iterator for Flaky {
+        type Item = u32;
+
+        fn next(&mut self) -> Option<u32> {
+            self.0 += 1;
+            if self.0.is_multiple_of(2) {
+                None
+            } else {
+                Some(self.0)
+            }
+        }
+    }
+
+    #[test]
+    fn merge_keeps_returning_none_after_exhaustion() {
+        let mut merged = merge_sorted(vec![Flaky(0), Flaky(0)]);
+        assert_eq!(merged.next(), Some(1));
+        assert_eq!(merged.next(), Some(1));
+        for _ in 0..10 {
+            assert_eq!(merged.next(), None);
+        }
+    }
+
+    #[test]
+    fn merge_k_sorted_vecs_of_mixed_lengths() {
+        let vecs = vec![vec![5, 6], vec![], vec![1, 2, 3, 4, 7, 8], vec![0], vec![]];
+        let merged = merge_k_sorted_vecs(vecs);
+        assert_eq!(merged, vec![0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert!(merged.capacity() >= 9);
+    }
+
+    #[test]
+    fn merge_k_sorted_vecs_of_empty_vectors_is_empty() {
+        let vecs: Vec<Vec<i32>> = vec![vec![], vec![]];
+        assert!(merge_k_sorted_vecs(vecs).is_empty());
+        assert!(merge_k_sorted_vecs::<i32>(vec![]).is_empty());
+    }
+
+    #[test]
+    fn par_merge_of_no_slices_is_empty() {
+        assert!(par_merge_sorted::<i32>(vec![]).is_empty());
+    }
+
+    #[test]
+    fn par_merge_of_many_shards_matches_sort() {
+        let mut rng = thread_rng();
+        let shards: Vec<Vec<i32>> = (0..300)
+            .map(|_| {
+                let len = rng.gen_range(0..50);
+                let mut shard: Vec<i32> = (0..len).map(|_| rng.gen_range(-1000..1000)).collect();
+                shard.sort();
+                shard
+            })
+            .collect();
+
+        let mut expected: Vec<i32> = shards.concat();
+        expected.sort();
+
+        let slices = shards.iter().map(Vec::as_slice).collect();
+        assert_eq!(par_merge_sorted(slices), expected);
+    }
+
+    #[test]
+    fn par_merge_on_a_single_thread_matches_sort() {
+        let shards = [vec![1, 5, 9], vec![2, 6], vec![0, 3, 4, 7, 8]];
+        let slices: Vec<&[i32]> = shards.iter().map(Vec::as_slice).collect();
+        assert_eq!(merge_runs(&slices, 1), (0..10).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn merge_heaps_sorted_yields_sorted_union() {
+        let heaps = vec![
+            BinaryHeap::from_vec(vec![9, 1, 5]),
+            BinaryHeap::new(),
+            BinaryHeap::from_vec(vec![4, 4, 0, 12]),
+            BinaryHeap::from_vec(vec![7, 2]),
+        ];
+        let merged: Vec<i32> = merge_heaps_sorted(heaps).collect();
+        assert_eq!(merged, vec![0, 1, 2, 4, 4, 5, 7, 9, 12]);
+    }
+}
@@ -18,4 +18,28 @@ pub trait MinHeap<T: Ord> {
 
     /// Returns true if the heap is empty.
     fn is_empty(&self) -> bool;
+
+    /// Returns the elements of the heap in ascending order, by draining a clone of the heap.
+    fn sorted_elements(&self) -> Vec<T>
+    where
+        Self: Clone,
+        T: Clone,
+    {
+        let mut heap = self.clone();
+        let mut elements = Vec::with_capacity(heap.len());
+        while let Some(value) = heap.pop() {
+            elements.push(value);
+        }
+        elements
+    }
+
+    /// Returns true if both heaps hold the same elements, the same number of times,
+    /// whatever their types and internal layouts are. This drains a clone of both heaps.
+    fn equals_multiset<O: MinHeap<T> + Clone>(&self, other: &O) -> bool
+    where
+        Self: Clone,
+        T: Clone,
+    {
+        self.len() == other.len() && self.sorted_elements() == other.sorted_elements()
+    }
 }
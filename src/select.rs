@@ -0,0 +1,73 @@
+use crate::sift;
+use std::cmp::Ord;
+
+/// Returns the n-th smallest element (0-based) of the slice.
+/// Return None if n is out of bounds.
+///
+/// The first n + 1 positions of the slice are used as a bounded max-heap, so
+/// afterwards they hold the n + 1 smallest elements, with the result at index 0.
+pub fn nth_smallest_in_place<T: Ord>(slice: &mut [T], n: usize) -> Option<&T> {
+    if n >= slice.len() {
+        return None;
+    }
+
+    // Reversing the comparator turns the shared min-heap sifts into max-heap ones.
+    let mut greater_first = |a: &T, b: &T| b.cmp(a);
+    let (heap, rest) = slice.split_at_mut(n + 1);
+    sift::heapify(heap, &mut greater_first);
+
+    for value in rest.iter_mut() {
+        if *value < heap[0] {
+            std::mem::swap(value, &mut heap[0]);
+            sift::sift_down(heap, 0, &mut greater_first);
+        }
+    }
+
+    slice.first()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn out_of_bounds_returns_none() {
+        let mut values = vec![3, 1, 2];
+        assert!(nth_smallest_in_place(&mut values, 3).is_none());
+
+        let mut empty: Vec<i32> = vec![];
+        assert!(nth_smallest_in_place(&mut empty, 0).is_none());
+    }
+
+    #[test]
+    fn finds_the_minimum_and_maximum() {
+        let mut values = vec![1, 10, 3, -4123, 34, 100, 124];
+        assert_eq!(*nth_smallest_in_place(&mut values, 0).unwrap(), -4123);
+        assert_eq!(*nth_smallest_in_place(&mut values, 6).unwrap(), 124);
+    }
+
+    #[test]
+    fn matches_sorted_copy() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..200).map(|_| rng.gen_range(-50..50)).collect();
+
+        let mut ordered = values.clone();
+        ordered.sort();
+
+        for (n, expected) in ordered.iter().enumerate() {
+            let mut scratch = values.clone();
+            assert_eq!(nth_smallest_in_place(&mut scratch, n), Some(expected));
+        }
+    }
+
+    #[test]
+    fn prefix_holds_the_smallest_elements() {
+        let mut values = vec![9, 4, 7, 1, 8, 2, 6, 3, 5, 0];
+        nth_smallest_in_place(&mut values, 3);
+
+        let mut prefix = values[..4].to_vec();
+        prefix.sort();
+        assert_eq!(prefix, vec![0, 1, 2, 3]);
+    }
+}
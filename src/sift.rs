@@ -0,0 +1,378 @@
+use std::cmp::Ordering;
+use std::mem::ManuallyDrop;
+use std::ptr;
+
+// Sift helpers shared by the array-backed heaps. They operate on a slice laid out
+// as a binary tree, where the sons of index i are 2i + 1 and 2i + 2, and order the
+// elements with a comparator, the smallest element being at the root.
+
+// The index helpers return None instead of wrapping around on overflow,
+// in which case the son can't exist.
+pub(crate) fn left_son(index: usize) -> Option<usize> {
+    index.checked_mul(2)?.checked_add(1)
+}
+pub(crate) fn right_son(index: usize) -> Option<usize> {
+    index.checked_mul(2)?.checked_add(2)
+}
+pub(crate) fn father(index: usize) -> Option<usize> {
+    if index == 0 {
+        None
+    } else {
+        Some((index - 1) / 2)
+    }
+}
+
+// A hole in a slice: an index whose element was moved out.
+// The element is read out once with ptr::read and kept in a ManuallyDrop, so it
+// is never dropped twice. When the hole is dropped, the element is written back
+// into the current position of the hole, so the slice is whole again even if a
+// comparison panics. Moving the hole copies a single element, while Vec::swap
+// would copy three.
+struct Hole<'a, T> {
+    data: &'a mut [T],
+    element: ManuallyDrop<T>,
+    pos: usize,
+}
+
+impl<'a, T> Hole<'a, T> {
+    // Creates a hole at the given index.
+    // Safety: pos must be in bounds.
+    unsafe fn new(data: &'a mut [T], pos: usize) -> Self {
+        debug_assert!(pos < data.len());
+        let element = ptr::read(data.as_ptr().add(pos));
+        Hole {
+            data,
+            element: ManuallyDrop::new(element),
+            pos,
+        }
+    }
+
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    // Returns the element that was moved out.
+    fn element(&self) -> &T {
+        &self.element
+    }
+
+    // Returns the element at the given index.
+    // Bounds are only checked in debug builds, as this sits in the hot sift loops.
+    // Safety: index must be in bounds and different from the hole position.
+    unsafe fn get(&self, index: usize) -> &T {
+        debug_assert!(index != self.pos);
+        if cfg!(debug_assertions) {
+            &self.data[index]
+        } else {
+            self.data.get_unchecked(index)
+        }
+    }
+
+    // Moves the element at the given index into the hole, which moves to that index.
+    // Safety: index must be in bounds and different from the hole position.
+    unsafe fn move_to(&mut self, index: usize) {
+        debug_assert!(index < self.data.len());
+        debug_assert!(index != self.pos);
+        let base = self.data.as_mut_ptr();
+        ptr::copy_nonoverlapping(base.add(index), base.add(self.pos), 1);
+        self.pos = index;
+    }
+}
+
+impl<T> Drop for Hole<'_, T> {
+    fn drop(&mut self) {
+        // Fill the hole back in.
+        unsafe {
+            let pos = self.pos;
+            ptr::copy_nonoverlapping(&*self.element, self.data.as_mut_ptr().add(pos), 1);
+        }
+    }
+}
+
+// Turns the debug total order check off, so tests can count comparisons as in release builds.
+#[cfg(test)]
+thread_local! {
+    static SKIP_TOTAL_ORDER_CHECK: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+// Runs f without the debug total order check.
+#[cfg(test)]
+pub(crate) fn without_total_order_check<R>(f: impl FnOnce() -> R) -> R {
+    SKIP_TOTAL_ORDER_CHECK.with(|skip| skip.set(true));
+    let result = f();
+    SKIP_TOTAL_ORDER_CHECK.with(|skip| skip.set(false));
+    result
+}
+
+// In debug builds, panics if comparing the two values the other way doesn't give the
+// reverse of the ordering that stopped a sift, which means the comparator is not a
+// total order. This costs one extra comparison where each sift stops.
+fn debug_check_total_order<T, F>(ordering: Ordering, a: &T, b: &T, cmp: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    #[cfg(test)]
+    if SKIP_TOTAL_ORDER_CHECK.with(|skip| skip.get()) {
+        return;
+    }
+
+    if cfg!(debug_assertions) {
+        assert!(
+            cmp(b, a) == ordering.reverse(),
+            "inconsistent Ord implementation: comparing two values both ways gave contradictory results"
+        );
+    }
+}
+
+// Moves the value at the given index up until its father is not greater.
+// The value is moved out once, the fathers are shifted down into the hole
+// and the value is written back once at its final position. This is a single
+// move per level, instead of the three moves done by a swap.
+pub(crate) fn sift_up<T, F>(values: &mut [T], index: usize, cmp: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // Safety: the caller passes an index in bounds. The hole position only ever
+    // decreases, to the father of the previous position, so both the hole and
+    // f = father(pos) < pos stay in bounds, and f is never the hole itself.
+    unsafe {
+        let mut hole = Hole::new(values, index);
+        while let Some(f) = father(hole.pos()) {
+            let ordering = cmp(hole.element(), hole.get(f));
+            if ordering != Ordering::Less {
+                debug_check_total_order(ordering, hole.element(), hole.get(f), cmp);
+                break;
+            }
+            hole.move_to(f);
+        }
+    }
+}
+
+// Moves the value at the given index down until none of its children is smaller.
+// Like sift_up, the smaller child is shifted up into a moving hole and the value
+// is written back only once, at its final position.
+pub(crate) fn sift_down<T, F>(values: &mut [T], index: usize, cmp: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let len = values.len();
+    if index >= len {
+        return;
+    }
+
+    // Safety: the caller passes an index in bounds, or the index is past the
+    // end and nothing is done. left_son and right_son are only accessed after being
+    // checked against len, and a son is always greater than the hole position.
+    // The hole only moves to such a son, so it stays in bounds.
+    unsafe {
+        let mut hole = Hole::new(values, index);
+        loop {
+            // Stop if the current node doesn't have any children.
+            let left_son = match left_son(hole.pos()) {
+                Some(son) if son < len => son,
+                _ => break,
+            };
+
+            // Find the smallest child.
+            let mut smallest = left_son;
+            if let Some(right_son) = right_son(hole.pos()).filter(|&son| son < len) {
+                if cmp(hole.get(right_son), hole.get(left_son)) == Ordering::Less {
+                    smallest = right_son;
+                }
+            }
+
+            // Stop if the current node is where it's supposed to be.
+            let ordering = cmp(hole.element(), hole.get(smallest));
+            if ordering != Ordering::Greater {
+                debug_check_total_order(ordering, hole.element(), hole.get(smallest), cmp);
+                break;
+            }
+
+            hole.move_to(smallest);
+        }
+    }
+}
+
+// Establishes the heap property over the whole slice in O(n), by sifting
+// down every node that has children, starting from the last one.
+pub(crate) fn heapify<T, F>(values: &mut [T], cmp: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for index in (0..values.len() / 2).rev() {
+        sift_down(values, index, cmp);
+    }
+}
+
+// Returns true if no value in the slice is smaller than its father.
+pub(crate) fn is_heap<T, F>(values: &[T], cmp: &mut F) -> bool
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    (1..values.len())
+        .all(|index| cmp(&values[(index - 1) / 2], &values[index]) != Ordering::Greater)
+}
+
+/// Establishes the min-heap property over the slice with the given comparator, in O(n).
+/// The smallest element ends up first, and the sons of index i are 2i + 1 and 2i + 2.
+pub fn make_heap<T, F: Fn(&T, &T) -> Ordering>(slice: &mut [T], cmp: F) {
+    heapify(slice, &mut |a, b| cmp(a, b));
+}
+
+/// Moves the last element of the slice into place, in O(log n).
+/// The rest of the slice must already be a heap, and the whole slice is one afterwards.
+pub fn push_heap<T, F: Fn(&T, &T) -> Ordering>(slice: &mut [T], cmp: F) {
+    if let Some(last) = slice.len().checked_sub(1) {
+        sift_up(slice, last, &mut |a, b| cmp(a, b));
+    }
+}
+
+/// Moves the smallest element of the heap to the end of the slice, in O(log n).
+/// The slice must be a heap, and all but its last element form a heap afterwards.
+pub fn pop_heap<T, F: Fn(&T, &T) -> Ordering>(slice: &mut [T], cmp: F) {
+    if let Some(last) = slice.len().checked_sub(1) {
+        slice.swap(0, last);
+        sift_down(&mut slice[..last], 0, &mut |a, b| cmp(a, b));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+    use std::cmp::Reverse;
+    use std::panic::{self, AssertUnwindSafe};
+
+    fn random_values(len: usize) -> Vec<i32> {
+        let mut rng = thread_rng();
+        (0..len).map(|_| rng.gen_range(-100..100)).collect()
+    }
+
+    #[test]
+    fn son_helpers_do_not_wrap_around() {
+        assert_eq!(left_son(0), Some(1));
+        assert_eq!(right_son(0), Some(2));
+        assert_eq!(left_son(10), Some(21));
+        assert_eq!(right_son(10), Some(22));
+
+        let boundary = usize::MAX / 2;
+        assert_eq!(left_son(boundary), Some(usize::MAX));
+        assert_eq!(right_son(boundary), None);
+        assert_eq!(left_son(boundary + 1), None);
+        assert_eq!(right_son(boundary + 1), None);
+        assert_eq!(left_son(usize::MAX), None);
+        assert_eq!(right_son(usize::MAX), None);
+    }
+
+    #[test]
+    fn father_matches_previous_formula_and_does_not_overflow() {
+        assert_eq!(father(0), None);
+        for index in 1..10000usize {
+            assert_eq!(father(index), Some(index.div_ceil(2) - 1));
+        }
+        assert_eq!(father(usize::MAX), Some(usize::MAX / 2));
+    }
+
+    #[test]
+    fn heapify_builds_heap_for_any_comparator() {
+        for len in 0..100 {
+            let mut values = random_values(len);
+            heapify(&mut values, &mut i32::cmp);
+            assert!(is_heap(&values, &mut i32::cmp));
+            assert_eq!(values.first(), values.iter().min());
+
+            // A reversed comparator builds a max-heap instead.
+            let mut reversed = |a: &i32, b: &i32| b.cmp(a);
+            heapify(&mut values, &mut reversed);
+            assert!(is_heap(&values, &mut reversed));
+            assert_eq!(values.first(), values.iter().max());
+        }
+    }
+
+    #[test]
+    fn sift_up_restores_heap_after_push() {
+        let mut values = vec![];
+        for value in random_values(300) {
+            values.push(value);
+            let last = values.len() - 1;
+            sift_up(&mut values, last, &mut i32::cmp);
+            assert!(is_heap(&values, &mut i32::cmp));
+        }
+    }
+
+    #[test]
+    fn sift_down_restores_heap_after_root_changes() {
+        let mut values = random_values(300);
+        heapify(&mut values, &mut i32::cmp);
+        let mut sorted = vec![];
+        while !values.is_empty() {
+            sorted.push(values.swap_remove(0));
+            sift_down(&mut values, 0, &mut i32::cmp);
+            assert!(is_heap(&values, &mut i32::cmp));
+        }
+        assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+
+        // An index past the end is ignored.
+        sift_down(&mut values, 5, &mut i32::cmp);
+    }
+
+    #[test]
+    fn is_heap_detects_violations() {
+        assert!(is_heap(&[] as &[i32], &mut i32::cmp));
+        assert!(is_heap(&[1, 2, 3, 2, 2], &mut i32::cmp));
+        assert!(!is_heap(&[1, 2, 3, 1, 2], &mut i32::cmp));
+        assert!(!is_heap(&[3, 2], &mut i32::cmp));
+    }
+
+    #[test]
+    fn comparator_panic_leaves_every_element() {
+        let mut values: Vec<Reverse<String>> = (0..64).map(|i| Reverse(i.to_string())).collect();
+        let mut calls = 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            heapify(
+                &mut values,
+                &mut |a: &Reverse<String>, b: &Reverse<String>| {
+                    calls += 1;
+                    if calls == 40 {
+                        panic!("comparison failed");
+                    }
+                    a.cmp(b)
+                },
+            );
+        }));
+        assert!(result.is_err());
+
+        let mut remaining: Vec<i32> = values.iter().map(|v| v.0.parse().unwrap()).collect();
+        remaining.sort();
+        assert_eq!(remaining, (0..64).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn heap_functions_on_a_borrowed_buffer() {
+        let mut buffer = random_values(200);
+        let values = buffer.clone();
+        let slice: &mut [i32] = &mut buffer;
+        make_heap(slice, i32::cmp);
+        assert!(is_heap(slice, &mut i32::cmp));
+
+        // Pop everything: the minimum goes right after the shrinking heap.
+        for end in (1..=slice.len()).rev() {
+            pop_heap(&mut slice[..end], i32::cmp);
+            assert!(is_heap(&slice[..end - 1], &mut i32::cmp));
+            assert!(slice[..end - 1].iter().all(|v| *v >= slice[end - 1]));
+        }
+        let mut expected = values.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(buffer, expected);
+
+        // Push everything back, one element at a time.
+        for end in 1..=buffer.len() {
+            push_heap(&mut buffer[..end], |a: &i32, b: &i32| b.cmp(a));
+            assert!(is_heap(&buffer[..end], &mut |a: &i32, b: &i32| b.cmp(a)));
+        }
+        assert_eq!(buffer.first(), values.iter().max());
+
+        push_heap(&mut [] as &mut [i32], i32::cmp);
+        pop_heap(&mut [] as &mut [i32], i32::cmp);
+    }
+}
@@ -0,0 +1,181 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Sorts the slice in ascending order with merge-insertion (the Ford-Johnson algorithm),
+/// which makes close to the minimum possible number of comparisons.
+/// This is meant for elements that are very expensive to compare: it optimizes the
+/// number of comparisons, not the number of moves, which can be O(n^2). The sort is not stable.
+pub fn comparison_minimal_sort<T, F: Fn(&T, &T) -> Ordering>(slice: &mut [T], cmp: F) {
+    let items: Vec<usize> = (0..slice.len()).collect();
+    let order = merge_insertion(&items, &mut |a, b| cmp(&slice[a], &slice[b]));
+    apply_order(slice, &order);
+}
+
+// Returns the given indices sorted with the comparator, using merge-insertion.
+fn merge_insertion<F: FnMut(usize, usize) -> Ordering>(items: &[usize], cmp: &mut F) -> Vec<usize> {
+    if items.len() <= 1 {
+        return items.to_vec();
+    }
+
+    // Compare the items in pairs, and sort the larger item of every pair recursively.
+    let mut partner = HashMap::new();
+    let mut larger = Vec::with_capacity(items.len() / 2);
+    for pair in items.chunks_exact(2) {
+        let (small, large) = match cmp(pair[0], pair[1]) {
+            Ordering::Greater => (pair[1], pair[0]),
+            _ => (pair[0], pair[1]),
+        };
+        partner.insert(large, small);
+        larger.push(large);
+    }
+    let larger = merge_insertion(&larger, cmp);
+
+    // The partner of the smallest large item is smaller than it, so it goes first for free.
+    // Every other smaller item is bounded by its partner, which is already in the chain.
+    let mut chain = Vec::with_capacity(items.len());
+    chain.push(partner[&larger[0]]);
+    chain.extend_from_slice(&larger);
+
+    let mut pending: Vec<(usize, Option<usize>)> = larger
+        .iter()
+        .map(|large| (partner[large], Some(*large)))
+        .collect();
+    if items.len() % 2 == 1 {
+        pending.push((items[items.len() - 1], None));
+    }
+
+    // Insert the pending items in groups whose sizes follow the Jacobsthal numbers, each group
+    // from its last item backwards. Every item is then binary searched in a prefix of the chain
+    // with a length of at most 2^k - 1, which is what keeps the comparisons minimal.
+    let (mut previous, mut jacobsthal) = (1, 1);
+    let mut group_end = 1;
+    while group_end < pending.len() {
+        (previous, jacobsthal) = (jacobsthal, jacobsthal + 2 * previous);
+        let start = group_end;
+        group_end = jacobsthal.min(pending.len());
+        for &(item, bound) in pending[start..group_end].iter().rev() {
+            let end = match bound {
+                Some(bound) => chain.iter().position(|&c| c == bound).unwrap(),
+                None => chain.len(),
+            };
+            let position = binary_search(&chain[..end], item, cmp);
+            chain.insert(position, item);
+        }
+    }
+    chain
+}
+
+// Returns the position at which the item should be inserted in the sorted chain.
+fn binary_search<F: FnMut(usize, usize) -> Ordering>(
+    chain: &[usize],
+    item: usize,
+    cmp: &mut F,
+) -> usize {
+    let (mut low, mut high) = (0, chain.len());
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if cmp(item, chain[middle]) == Ordering::Less {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    low
+}
+
+// Reorders the slice so that the element at index i is the one that was at order[i].
+fn apply_order<T>(slice: &mut [T], order: &[usize]) {
+    for i in 0..slice.len() {
+        // Elements before i were already moved. Follow where the wanted element went.
+        let mut source = order[i];
+        while source < i {
+            source = order[source];
+        }
+        slice.swap(i, source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_heap::BinaryHeap;
+    use crate::sift;
+    use crate::testing::{comparison_count, reset_comparisons, CountingOrd};
+    use rand::prelude::*;
+
+    // The worst case number of comparisons made by merge-insertion on n elements.
+    fn ford_johnson_bound(n: usize) -> usize {
+        (1..=n)
+            .map(|k| (3.0 * k as f64 / 4.0).log2().ceil() as usize)
+            .sum()
+    }
+
+    #[test]
+    fn sorts_like_sort_by() {
+        let mut rng = thread_rng();
+        for len in 0..60 {
+            for _ in 0..10 {
+                let mut values: Vec<i32> = (0..len).map(|_| rng.gen_range(-10..10)).collect();
+                let mut expected = values.clone();
+                expected.sort();
+                comparison_minimal_sort(&mut values, i32::cmp);
+                assert_eq!(values, expected);
+            }
+        }
+
+        let mut words = vec!["pear", "fig", "banana", "kiwi", "apple"];
+        comparison_minimal_sort(&mut words, |a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        assert_eq!(words, vec!["banana", "apple", "kiwi", "pear", "fig"]);
+    }
+
+    #[test]
+    fn moves_elements_that_are_not_clone() {
+        let mut values: Vec<Box<i32>> = [5, 3, 9, 1, 7, 2].into_iter().map(Box::new).collect();
+        comparison_minimal_sort(&mut values, |a, b| a.cmp(b));
+        let values: Vec<i32> = values.into_iter().map(|v| *v).collect();
+        assert_eq!(values, vec![1, 2, 3, 5, 7, 9]);
+    }
+
+    #[test]
+    fn comparisons_stay_within_the_ford_johnson_bound() {
+        let mut rng = thread_rng();
+        for n in 1..40 {
+            let bound = ford_johnson_bound(n);
+            for _ in 0..20 {
+                let mut values: Vec<CountingOrd<u32>> =
+                    (0..n).map(|_| CountingOrd(rng.gen())).collect();
+                reset_comparisons();
+                comparison_minimal_sort(&mut values, |a, b| a.cmp(b));
+                assert!(
+                    comparison_count() <= bound,
+                    "{} comparisons for {}",
+                    comparison_count(),
+                    n
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn makes_fewer_comparisons_than_heapsort() {
+        let mut rng = thread_rng();
+        for n in 4..16 {
+            let mut ours = 0;
+            let mut heapsort = 0;
+            for _ in 0..50 {
+                let mut values: Vec<CountingOrd<u32>> =
+                    (0..n).map(|_| CountingOrd(rng.gen())).collect();
+                let copy = values.clone();
+
+                reset_comparisons();
+                comparison_minimal_sort(&mut values, |a, b| a.cmp(b));
+                ours += comparison_count();
+
+                reset_comparisons();
+                sift::without_total_order_check(|| BinaryHeap::from_vec(copy).into_sorted_vec());
+                heapsort += comparison_count();
+            }
+            assert!(ours < heapsort, "{} against {} for {}", ours, heapsort, n);
+        }
+    }
+}
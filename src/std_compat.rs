@@ -0,0 +1,111 @@
+use crate::MinHeap;
+use std::cmp::{Ord, Reverse};
+use std::marker::PhantomData;
+
+/// An adapter that gives any min-heap of Reverse<T> the max-heap API of
+/// std::collections::BinaryHeap, to ease migrating code written against it.
+pub struct StdCompat<T: Ord, H: MinHeap<Reverse<T>>> {
+    heap: H,
+    values: PhantomData<T>,
+}
+
+impl<T: Ord, H: MinHeap<Reverse<T>>> StdCompat<T, H> {
+    /// Returns an adapter around the given heap.
+    pub fn new(heap: H) -> Self {
+        StdCompat {
+            heap,
+            values: PhantomData,
+        }
+    }
+
+    /// Consumes the adapter and returns the wrapped heap.
+    pub fn into_inner(self) -> H {
+        self.heap
+    }
+
+    /// Insert a new value in the heap.
+    pub fn push(&mut self, value: T) {
+        self.heap.push(Reverse(value));
+    }
+
+    /// Returns an imutable borrow to the largest element in the heap.
+    /// Return None if the heap is empty.
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.top().map(|Reverse(value)| value)
+    }
+
+    /// Returns ownership of the largest element in the heap and deletes it from the heap.
+    /// Return None if the heap is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        self.heap.pop().map(|Reverse(value)| value)
+    }
+
+    /// Returns the number of elements in the heap.
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Returns true if the heap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Consumes the heap and returns its elements in ascending order, like std does.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut values = Vec::with_capacity(self.len());
+        while let Some(value) = self.pop() {
+            values.push(value);
+        }
+        values.reverse();
+        values
+    }
+}
+
+impl<T: Ord, H: MinHeap<Reverse<T>> + Default> Default for StdCompat<T, H> {
+    fn default() -> Self {
+        Self::new(H::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena_binary_heap::ArenaBinaryHeap;
+    use crate::binary_heap::BinaryHeap;
+    use rand::prelude::*;
+
+    #[test]
+    fn pops_like_std() {
+        let mut rng = thread_rng();
+        let mut ours: StdCompat<i32, BinaryHeap<_>> = StdCompat::default();
+        let mut theirs = std::collections::BinaryHeap::new();
+
+        for _ in 0..10000 {
+            if rng.gen_bool(0.4) {
+                assert_eq!(ours.pop(), theirs.pop());
+            } else {
+                let v = rng.gen_range(-100..100);
+                ours.push(v);
+                theirs.push(v);
+            }
+            assert_eq!(ours.peek(), theirs.peek());
+            assert_eq!(ours.len(), theirs.len());
+        }
+        assert_eq!(ours.into_sorted_vec(), theirs.into_sorted_vec());
+    }
+
+    #[test]
+    fn wraps_any_min_heap() {
+        let mut h = StdCompat::new(ArenaBinaryHeap::new());
+        assert!(h.is_empty());
+        assert!(h.peek().is_none());
+        for v in [3, 9, 1, 7] {
+            h.push(v);
+        }
+        assert_eq!(h.peek(), Some(&9));
+        assert_eq!(h.pop(), Some(9));
+
+        let inner = h.into_inner();
+        assert_eq!(inner.top(), Some(&Reverse(7)));
+    }
+}
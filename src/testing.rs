@@ -0,0 +1,167 @@
+use crate::MinHeap;
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+/// Pushes the input into the heap, pops everything and asserts that the
+/// values come out in ascending order, matching a sorted copy of the input.
+pub fn assert_sorts_correctly<H: MinHeap<i32>>(mut h: H, input: &[i32]) {
+    let start = h.len();
+    assert_eq!(start, 0, "the heap must start out empty");
+
+    for (i, value) in input.iter().enumerate() {
+        h.push(*value);
+        assert_eq!(h.len(), i + 1);
+    }
+
+    let mut expected = input.to_vec();
+    expected.sort();
+
+    let mut popped = Vec::with_capacity(input.len());
+    while let Some(value) = h.pop() {
+        popped.push(value);
+    }
+    assert_eq!(popped, expected);
+    assert!(h.is_empty());
+}
+
+/// Returns true if the comparator is a total order over the samples: every sample
+/// compares equal to itself, swapping the arguments reverses the result, and the
+/// order is transitive. Every triple of samples is checked, so this is O(n^3).
+pub fn is_total_order<T, F: Fn(&T, &T) -> Ordering>(samples: &[T], cmp: F) -> bool {
+    let reflexive = samples.iter().all(|a| cmp(a, a) == Ordering::Equal);
+    let antisymmetric = samples
+        .iter()
+        .all(|a| samples.iter().all(|b| cmp(a, b) == cmp(b, a).reverse()));
+    let transitive = samples.iter().all(|a| {
+        samples.iter().all(|b| {
+            let ab = cmp(a, b);
+            ab == Ordering::Greater
+                || samples.iter().all(|c| {
+                    let bc = cmp(b, c);
+                    // a <= b <= c implies a <= c, and a < c unless both are equalities.
+                    match (ab, bc) {
+                        (_, Ordering::Greater) => true,
+                        (Ordering::Equal, Ordering::Equal) => cmp(a, c) == Ordering::Equal,
+                        _ => cmp(a, c) == Ordering::Less,
+                    }
+                })
+        })
+    });
+    reflexive && antisymmetric && transitive
+}
+
+thread_local! {
+    static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Resets the CountingOrd comparison counter of the current thread to zero.
+pub fn reset_comparisons() {
+    COMPARISONS.with(|c| c.set(0));
+}
+
+/// Returns the number of CountingOrd comparisons made on the current thread since the last reset.
+pub fn comparison_count() -> usize {
+    COMPARISONS.with(|c| c.get())
+}
+
+/// A wrapper that counts how many times values are compared, see comparison_count.
+/// The counter is per thread, and shared by every CountingOrd type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CountingOrd<T>(pub T);
+
+impl<T: Ord> PartialOrd for CountingOrd<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for CountingOrd<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        COMPARISONS.with(|c| c.set(c.get() + 1));
+        self.0.cmp(&other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_heap::BinaryHeap;
+    use crate::sift;
+    use rand::prelude::*;
+
+    #[test]
+    fn counts_comparisons() {
+        reset_comparisons();
+        assert!(CountingOrd(1) < CountingOrd(2));
+        assert!(CountingOrd(3) >= CountingOrd(2));
+        assert_eq!(CountingOrd(1).cmp(&CountingOrd(1)), Ordering::Equal);
+        assert_eq!(comparison_count(), 3);
+
+        reset_comparisons();
+        assert_eq!(comparison_count(), 0);
+    }
+
+    #[test]
+    fn heap_comparisons_are_within_n_log_n() {
+        let mut rng = thread_rng();
+        let n: usize = 10000;
+        let log_n = n.ilog2() as usize + 1;
+        let mut h = BinaryHeap::new();
+
+        reset_comparisons();
+        sift::without_total_order_check(|| {
+            for _ in 0..n {
+                h.push(CountingOrd(rng.gen::<i32>()));
+            }
+        });
+        let pushes = comparison_count();
+        // Random pushes only move up a constant number of levels on average.
+        assert!(pushes <= 4 * n * log_n, "{} comparisons", pushes);
+
+        reset_comparisons();
+        sift::without_total_order_check(|| while h.pop().is_some() {});
+        let pops = comparison_count();
+        // A pop compares the sinking value against both sons on every level.
+        assert!(pops <= 4 * n * log_n, "{} comparisons", pops);
+        assert!(pops >= n, "{} comparisons", pops);
+    }
+
+    #[test]
+    fn accepts_total_orders() {
+        let samples = [3, -1, 4, 1, 5, 9, 2, 6, 5, 3];
+        assert!(is_total_order(&samples, i32::cmp));
+        assert!(is_total_order(&samples, |a: &i32, b: &i32| b.cmp(a)));
+        assert!(is_total_order(&samples, |a: &i32, b: &i32| {
+            (a % 3).cmp(&(b % 3))
+        }));
+        assert!(is_total_order(&[] as &[i32], i32::cmp));
+    }
+
+    #[test]
+    fn rejects_broken_comparators() {
+        let samples = [0, 1, 2, 3];
+        // Not reflexive.
+        assert!(!is_total_order(&samples, |_: &i32, _: &i32| Ordering::Less));
+        // Not antisymmetric.
+        assert!(!is_total_order(&samples, |a: &i32, b: &i32| {
+            if a == b {
+                Ordering::Equal
+            } else {
+                Ordering::Less
+            }
+        }));
+        // Not transitive: rock, paper, scissors.
+        assert!(!is_total_order(&samples[..3], |a: &i32, b: &i32| {
+            match (b - a).rem_euclid(3) {
+                0 => Ordering::Equal,
+                1 => Ordering::Less,
+                _ => Ordering::Greater,
+            }
+        }));
+        // Partial comparisons of floats, treating NaN as equal to everything.
+        let floats = [1.0, f64::NAN, 2.0];
+        assert!(!is_total_order(&floats, |a: &f64, b: &f64| {
+            a.partial_cmp(b).unwrap_or(Ordering::Equal)
+        }));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::binary_heap::BinaryHeap;
+use crate::MinHeap;
+use std::cmp::Ord;
+
+/// Extension trait for selecting the largest items of an iterator.
+pub trait TopKExt: Iterator + Sized {
+    /// Returns the k largest items, sorted in descending order.
+    /// Only O(k) items are kept in memory at any time.
+    fn top_k(self, k: usize) -> Vec<Self::Item>
+    where
+        Self::Item: Ord;
+}
+
+impl<I: Iterator> TopKExt for I {
+    fn top_k(self, k: usize) -> Vec<Self::Item>
+    where
+        Self::Item: Ord,
+    {
+        if k == 0 {
+            return vec![];
+        }
+
+        // The smallest of the k largest items seen so far is on top.
+        let mut heap = BinaryHeap::new();
+        for item in self {
+            if heap.len() < k {
+                heap.push(item);
+            } else if item > *heap.top().unwrap() {
+                heap.pop();
+                heap.push(item);
+            }
+        }
+
+        let mut result = Vec::with_capacity(heap.len());
+        while let Some(item) = heap.pop() {
+            result.push(item);
+        }
+        result.reverse();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn top_zero_is_empty() {
+        assert!((0..100).top_k(0).is_empty());
+    }
+
+    #[test]
+    fn top_k_of_short_stream_returns_everything() {
+        assert_eq!(vec![3, 1, 2].into_iter().top_k(10), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn top_k_of_large_range() {
+        let top = (0..1_000_000u64).top_k(10);
+        let expected: Vec<u64> = (999_990..1_000_000).rev().collect();
+        assert_eq!(top, expected);
+    }
+
+    #[test]
+    fn top_k_matches_sorted_copy() {
+        let mut rng = thread_rng();
+        let values: Vec<i32> = (0..1000).map(|_| rng.gen_range(-100..100)).collect();
+
+        let mut ordered = values.clone();
+        ordered.sort_by(|a, b| b.cmp(a));
+        ordered.truncate(25);
+
+        assert_eq!(values.into_iter().top_k(25), ordered);
+    }
+}
@@ -0,0 +1,149 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+// Defines a totally ordered wrapper around a float type.
+macro_rules! total_float {
+    ($(#[$meta:meta])* $name:ident, $float:ty) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name(pub $float);
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+
+        impl From<$float> for $name {
+            fn from(value: $float) -> Self {
+                $name(value)
+            }
+        }
+
+        impl From<$name> for $float {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+total_float!(
+    /// A f64 wrapper that is totally ordered, so it can be stored in a heap.
+    /// Values are ordered by f64::total_cmp: -0.0 is smaller than 0.0, positive NaN
+    /// is greater than infinity, and negative NaN is smaller than negative infinity.
+    TotalF64,
+    f64
+);
+
+total_float!(
+    /// A f32 wrapper that is totally ordered, so it can be stored in a heap.
+    /// Values are ordered by f32::total_cmp, with the same rules as TotalF64.
+    TotalF32,
+    f32
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary_heap::BinaryHeap;
+    use crate::MinHeap;
+
+    #[test]
+    fn converts_to_and_from_f64() {
+        let value: TotalF64 = 2.5.into();
+        let back: f64 = value.into();
+        assert_eq!(back, 2.5);
+    }
+
+    #[test]
+    fn nan_equals_itself() {
+        assert_eq!(TotalF64(f64::NAN), TotalF64(f64::NAN));
+        assert_ne!(TotalF64(-0.0), TotalF64(0.0));
+    }
+
+    #[test]
+    fn heap_pops_floats_in_total_order() {
+        let mut h = BinaryHeap::new();
+        let values = [
+            3.5,
+            f64::NAN,
+            -1.0,
+            f64::INFINITY,
+            0.0,
+            -0.0,
+            f64::NEG_INFINITY,
+            -f64::NAN,
+            1e-300,
+        ];
+        for v in values {
+            h.push(TotalF64::from(v));
+        }
+
+        let popped: Vec<f64> = std::iter::from_fn(|| h.pop()).map(f64::from).collect();
+        assert!(popped[0].is_nan() && popped[0].is_sign_negative());
+        assert_eq!(popped[1], f64::NEG_INFINITY);
+        assert_eq!(popped[2], -1.0);
+        assert!(popped[3] == 0.0 && popped[3].is_sign_negative());
+        assert!(popped[4] == 0.0 && popped[4].is_sign_positive());
+        assert_eq!(popped[5], 1e-300);
+        assert_eq!(popped[6], 3.5);
+        assert_eq!(popped[7], f64::INFINITY);
+        assert!(popped[8].is_nan() && popped[8].is_sign_positive());
+    }
+
+    #[test]
+    fn f32_converts_and_displays() {
+        let value: TotalF32 = 1.5f32.into();
+        assert_eq!(f32::from(value), 1.5);
+        assert_eq!(value.to_string(), "1.5");
+        assert_eq!(format!("{:?}", value), "TotalF32(1.5)");
+        assert_eq!(TotalF64(-0.25).to_string(), "-0.25");
+    }
+
+    #[test]
+    fn heap_pops_f32_in_total_order() {
+        let mut h = BinaryHeap::new();
+        let values = [
+            f32::NAN,
+            0.0,
+            f32::INFINITY,
+            -0.0,
+            -2.0,
+            f32::NEG_INFINITY,
+            7.0,
+        ];
+        for v in values {
+            h.push(TotalF32::from(v));
+        }
+
+        let popped: Vec<f32> = std::iter::from_fn(|| h.pop()).map(f32::from).collect();
+        assert_eq!(popped[0], f32::NEG_INFINITY);
+        assert_eq!(popped[1], -2.0);
+        assert!(popped[2] == 0.0 && popped[2].is_sign_negative());
+        assert!(popped[3] == 0.0 && popped[3].is_sign_positive());
+        assert_eq!(popped[4], 7.0);
+        assert_eq!(popped[5], f32::INFINITY);
+        assert!(popped[6].is_nan());
+    }
+}
@@ -0,0 +1,341 @@
+use crate::sift;
+use std::cmp::Ord;
+use std::collections::HashMap;
+
+/// A handle to an element of a TrackedBinaryHeap.
+/// It stays valid until the element is popped or removed.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Handle(u64);
+
+/// A tracked binary heap is a binary min-heap that hands out a handle for every pushed
+/// element, and keeps track of the position of every element as it is sifted.
+/// This allows elements to be looked up, decreased and removed through their handles,
+/// while keeping the elements in a single cache-friendly Vec.
+pub struct TrackedBinaryHeap<T: Ord> {
+    entries: Vec<(Handle, T)>,
+    // The index in entries of every live handle.
+    positions: HashMap<Handle, usize>,
+    next_handle: u64,
+}
+
+impl<T: Ord> TrackedBinaryHeap<T> {
+    /// Returns an empty tracked binary heap.
+    pub fn new() -> Self {
+        TrackedBinaryHeap {
+            entries: vec![],
+            positions: HashMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    /// Insert a new value in the heap and returns its handle.
+    pub fn push(&mut self, value: T) -> Handle {
+        let handle = Handle(self.next_handle);
+        self.next_handle += 1;
+
+        self.entries.push((handle, value));
+        let last = self.entries.len() - 1;
+        self.positions.insert(handle, last);
+        self.sift_up(last);
+        handle
+    }
+
+    /// Returns an imutable borrow to the smallest element in the heap.
+    /// Return None if the heap is empty.
+    pub fn top(&self) -> Option<&T> {
+        self.entries.first().map(|(_, value)| value)
+    }
+
+    /// Returns the handle of the smallest element in the heap.
+    /// Return None if the heap is empty.
+    pub fn top_handle(&self) -> Option<Handle> {
+        self.entries.first().map(|&(handle, _)| handle)
+    }
+
+    /// Returns ownership of the smallest element in the heap and deletes it from the heap.
+    /// Return None if the heap is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        let handle = self.top_handle()?;
+        self.remove(handle)
+    }
+
+    /// Returns an imutable borrow to the element with the given handle.
+    /// Return None if the element is no longer in the heap.
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        let &pos = self.positions.get(&handle)?;
+        Some(&self.entries[pos].1)
+    }
+
+    /// Returns true if the element with the given handle is still in the heap.
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.positions.contains_key(&handle)
+    }
+
+    /// Replaces the element with the given handle by a value that is not greater,
+    /// and moves it up to its new position, in O(log n).
+    /// Returns false if the element is no longer in the heap.
+    /// Panics if the new value is greater than the old one.
+    pub fn decrease_key(&mut self, handle: Handle, value: T) -> bool {
+        let Some(&pos) = self.positions.get(&handle) else {
+            return false;
+        };
+        assert!(
+            value <= self.entries[pos].1,
+            "decrease_key called with a greater value"
+        );
+        self.entries[pos].1 = value;
+        self.sift_up(pos);
+        true
+    }
+
+    /// Returns ownership of the element with the given handle and deletes it from the heap.
+    /// Return None if the element is no longer in the heap.
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let pos = self.positions.remove(&handle)?;
+        let (_, value) = self.entries.swap_remove(pos);
+
+        // The last element fills the gap, and can need to move either way.
+        if pos < self.entries.len() {
+            self.positions.insert(self.entries[pos].0, pos);
+            self.sift_up(pos);
+            self.sift_down(pos);
+        }
+        Some(value)
+    }
+
+    /// Keeps only the elements that match the predicate. The surviving elements keep their
+    /// handles, and the handles of the removed ones become stale.
+    /// The heap and the positions are rebuilt once afterwards, in O(n).
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let old_len = self.entries.len();
+        self.entries.retain(|(_, value)| f(value));
+        if self.entries.len() == old_len {
+            return;
+        }
+
+        sift::heapify(&mut self.entries, &mut |a, b| a.1.cmp(&b.1));
+        self.positions.clear();
+        for (pos, &(handle, _)) in self.entries.iter().enumerate() {
+            self.positions.insert(handle, pos);
+        }
+    }
+
+    /// Returns the number of elements in the heap.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the heap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Swaps two entries and updates their positions.
+    fn swap(&mut self, a: usize, b: usize) {
+        self.entries.swap(a, b);
+        self.positions.insert(self.entries[a].0, a);
+        self.positions.insert(self.entries[b].0, b);
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let father = (pos - 1) / 2;
+            if self.entries[pos].1 >= self.entries[father].1 {
+                break;
+            }
+            self.swap(pos, father);
+            pos = father;
+        }
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        let len = self.entries.len();
+        loop {
+            let left_son = 2 * pos + 1;
+            let right_son = 2 * pos + 2;
+
+            // Stop if the current node doesn't have any children.
+            if left_son >= len {
+                break;
+            }
+
+            // Find the smallest child.
+            let mut smallest = left_son;
+            if right_son < len && self.entries[right_son].1 < self.entries[left_son].1 {
+                smallest = right_son;
+            }
+
+            // Stop if the current node is where it's supposed to be.
+            if self.entries[pos].1 <= self.entries[smallest].1 {
+                break;
+            }
+
+            self.swap(pos, smallest);
+            pos = smallest;
+        }
+    }
+}
+
+impl<T: Ord> Default for TrackedBinaryHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    // Checks the heap property, and that the position map matches the entries.
+    fn is_consistent<T: Ord>(h: &TrackedBinaryHeap<T>) -> bool {
+        let ordered = (1..h.entries.len()).all(|i| h.entries[(i - 1) / 2].1 <= h.entries[i].1);
+        let tracked = h.positions.len() == h.entries.len()
+            && h.entries
+                .iter()
+                .enumerate()
+                .all(|(pos, (handle, _))| h.positions.get(handle) == Some(&pos));
+        ordered && tracked
+    }
+
+    #[test]
+    fn empty_heap_is_empty() {
+        let mut h = TrackedBinaryHeap::<i32>::new();
+        assert!(h.is_empty());
+        assert!(h.top().is_none());
+        assert!(h.top_handle().is_none());
+        assert!(h.pop().is_none());
+    }
+
+    #[test]
+    fn handles_stay_valid_until_popped() {
+        let mut h = TrackedBinaryHeap::new();
+        let handles: Vec<Handle> = [50, 20, 40, 10, 30]
+            .into_iter()
+            .map(|v| h.push(v))
+            .collect();
+        for (&handle, value) in handles.iter().zip([50, 20, 40, 10, 30]) {
+            assert_eq!(h.get(handle), Some(&value));
+        }
+
+        assert_eq!(h.top_handle(), Some(handles[3]));
+        assert_eq!(h.pop(), Some(10));
+        assert!(!h.contains(handles[3]));
+        assert_eq!(h.get(handles[3]), None);
+        assert_eq!(h.get(handles[0]), Some(&50));
+        assert!(is_consistent(&h));
+    }
+
+    #[test]
+    fn decrease_key_moves_element_up() {
+        let mut h = TrackedBinaryHeap::new();
+        let handles: Vec<Handle> = (0..20).map(|v| h.push(v * 10)).collect();
+
+        assert!(h.decrease_key(handles[15], -5));
+        assert!(is_consistent(&h));
+        assert_eq!(h.top(), Some(&-5));
+        assert_eq!(h.top_handle(), Some(handles[15]));
+
+        assert!(h.decrease_key(handles[7], 15));
+        assert_eq!(h.pop(), Some(-5));
+        assert_eq!(h.pop(), Some(0));
+        assert_eq!(h.pop(), Some(10));
+        assert_eq!(h.pop(), Some(15));
+
+        let popped = handles[0];
+        assert!(!h.decrease_key(popped, -100));
+    }
+
+    #[test]
+    #[should_panic]
+    fn decrease_key_panics_on_greater_value() {
+        let mut h = TrackedBinaryHeap::new();
+        let handle = h.push(5);
+        h.decrease_key(handle, 6);
+    }
+
+    #[test]
+    fn remove_keeps_positions_consistent() {
+        let mut h = TrackedBinaryHeap::new();
+        let handles: Vec<Handle> = [8, 3, 9, 1, 7, 2, 6]
+            .into_iter()
+            .map(|v| h.push(v))
+            .collect();
+        assert_eq!(h.remove(handles[4]), Some(7));
+        assert_eq!(h.remove(handles[4]), None);
+        assert!(is_consistent(&h));
+
+        let popped: Vec<i32> = std::iter::from_fn(|| h.pop()).collect();
+        assert_eq!(popped, vec![1, 2, 3, 6, 8, 9]);
+    }
+
+    #[test]
+    fn retain_keeps_surviving_handles() {
+        let mut h = TrackedBinaryHeap::new();
+        let handles: Vec<Handle> = (0..50).map(|v| h.push((v * 37) % 50)).collect();
+        h.retain(|v| v % 2 == 0);
+        assert_eq!(h.len(), 25);
+        assert!(is_consistent(&h));
+
+        for (i, &handle) in handles.iter().enumerate() {
+            let value = (i as i32 * 37) % 50;
+            if value % 2 == 0 {
+                assert_eq!(h.get(handle), Some(&value));
+            } else {
+                assert!(!h.contains(handle));
+                assert!(!h.decrease_key(handle, -1));
+            }
+        }
+
+        // A surviving handle can still be decreased to become the minimum.
+        let survivor = handles[2];
+        assert_eq!(h.get(survivor), Some(&24));
+        assert!(h.decrease_key(survivor, -1));
+        assert_eq!(h.top_handle(), Some(survivor));
+        assert!(is_consistent(&h));
+
+        let popped: Vec<i32> = std::iter::from_fn(|| h.pop()).collect();
+        let mut expected: Vec<i32> = (0..50).filter(|v| v % 2 == 0 && *v != 24).collect();
+        expected.insert(0, -1);
+        assert_eq!(popped, expected);
+    }
+
+    #[test]
+    fn fuzz_positions_stay_consistent() {
+        let mut rng = thread_rng();
+        let mut h = TrackedBinaryHeap::new();
+        let mut live: Vec<(Handle, i32)> = vec![];
+
+        for _ in 0..5000 {
+            match rng.gen_range(0..4) {
+                0 if !live.is_empty() => {
+                    let index = rng.gen_range(0..live.len());
+                    let (handle, value) = &mut live[index];
+                    *value -= rng.gen_range(0..100);
+                    assert!(h.decrease_key(*handle, *value));
+                }
+                1 if !live.is_empty() => {
+                    let (handle, value) = live.swap_remove(rng.gen_range(0..live.len()));
+                    assert_eq!(h.remove(handle), Some(value));
+                }
+                2 => {
+                    let min = live.iter().map(|&(_, v)| v).min();
+                    let top = h.top_handle();
+                    assert_eq!(h.pop(), min);
+                    if let Some(index) = live.iter().position(|&(handle, _)| Some(handle) == top) {
+                        live.swap_remove(index);
+                    }
+                }
+                _ => {
+                    let value = rng.gen_range(-1000..1000);
+                    live.push((h.push(value), value));
+                }
+            }
+            assert_eq!(h.len(), live.len());
+        }
+        assert!(is_consistent(&h));
+        for (handle, value) in live {
+            assert_eq!(h.get(handle), Some(&value));
+        }
+    }
+}
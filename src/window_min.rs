@@ -0,0 +1,115 @@
+use std::cmp::Ord;
+use std::collections::VecDeque;
+
+/// A sliding window that tracks the minimum of the last k pushed values.
+/// Every operation runs in amortized O(1) using a monotonic deque.
+pub struct WindowMin<T: Ord + Clone> {
+    // Candidates for the minimum, tagged by their position in the stream.
+    // The values are strictly increasing from front to back.
+    candidates: VecDeque<(usize, T)>,
+    window: usize,
+    pushed: usize,
+}
+
+impl<T: Ord + Clone> WindowMin<T> {
+    /// Returns an empty window of the given size.
+    /// Panics if the window size is zero.
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0, "window size must be positive");
+        WindowMin {
+            candidates: VecDeque::new(),
+            window,
+            pushed: 0,
+        }
+    }
+
+    /// Push a new value into the window, evicting the oldest one if the window is full.
+    pub fn push(&mut self, value: T) {
+        // Values that are not smaller than the new one can never be the minimum again.
+        while let Some((_, back)) = self.candidates.back() {
+            if *back < value {
+                break;
+            }
+            self.candidates.pop_back();
+        }
+        self.candidates.push_back((self.pushed, value));
+        self.pushed += 1;
+
+        // Evict the front candidate once it slides out of the window.
+        if let Some(&(position, _)) = self.candidates.front() {
+            if self.pushed - position > self.window {
+                self.candidates.pop_front();
+            }
+        }
+    }
+
+    /// Returns an imutable borrow to the smallest value in the window.
+    /// Return None if no values were pushed.
+    pub fn min(&self) -> Option<&T> {
+        self.candidates.front().map(|(_, value)| value)
+    }
+
+    /// Returns the window size.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    #[should_panic]
+    fn zero_window_panics() {
+        WindowMin::<i32>::new(0);
+    }
+
+    #[test]
+    fn empty_window_has_no_min() {
+        let w = WindowMin::<i32>::new(3);
+        assert!(w.min().is_none());
+    }
+
+    #[test]
+    fn min_of_known_sequence() {
+        let mut w = WindowMin::new(3);
+        let values = vec![4, 2, 12, 11, -5, 7, 8, 9, 10];
+        let expected = vec![4, 2, 2, 2, -5, -5, -5, 7, 8];
+
+        for (value, min) in values.into_iter().zip(expected) {
+            w.push(value);
+            assert_eq!(*w.min().unwrap(), min);
+        }
+    }
+
+    #[test]
+    fn huge_window_keeps_every_value() {
+        let mut w = WindowMin::new(usize::MAX);
+        for value in (0..100).rev() {
+            w.push(value);
+            assert_eq!(w.min(), Some(&value));
+        }
+        for value in 0..100 {
+            w.push(value);
+            assert_eq!(w.min(), Some(&0));
+        }
+    }
+
+    #[test]
+    fn min_matches_naive_window() {
+        let mut rng = thread_rng();
+        for window in 1..10 {
+            let mut w = WindowMin::new(window);
+            let values: Vec<i32> = (0..500).map(|_| rng.gen_range(-20..20)).collect();
+
+            for (i, value) in values.iter().enumerate() {
+                w.push(*value);
+                let start = (i + 1).saturating_sub(window);
+                let naive = values[start..=i].iter().min();
+                assert_eq!(w.min(), naive);
+            }
+        }
+    }
+}